@@ -0,0 +1,11 @@
+use naia_socket_shared::DisconnectReason;
+
+/// An event raised by the Client Socket about its connection to the Server
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ClientEvent {
+    /// The Server is considered disconnected
+    Disconnected(DisconnectReason),
+    /// The Server's epoch changed, so it has restarted since it was last
+    /// heard from & has forgotten this Client
+    ServerRestarted,
+}
@@ -1,35 +1,51 @@
-use naia_socket_shared::{link_condition_logic, LinkConditionerConfig, TimeQueue};
+use naia_socket_shared::{LinkConditioner, LinkConditionerHandle, LinkDirection};
 
 use super::{
-    error::NaiaClientSocketError, packet_receiver::PacketReceiverTrait, server_addr::ServerAddr,
+    conditioned_packet_sender::ConditionedPacketSender, error::NaiaClientSocketError,
+    packet_receiver::PacketReceiverTrait, server_addr::ServerAddr,
 };
 
 /// Used to receive packets from the Client Socket
 #[derive(Clone)]
 pub struct ConditionedPacketReceiver {
     inner_receiver: Box<dyn PacketReceiverTrait>,
-    link_conditioner_config: LinkConditionerConfig,
-    time_queue: TimeQueue<Box<[u8]>>,
+    link_conditioner: LinkConditioner<Box<[u8]>>,
     last_payload: Option<Box<[u8]>>,
+    outgoing_sender: Option<ConditionedPacketSender>,
 }
 
 impl ConditionedPacketReceiver {
     /// Creates a new ConditionedPacketReceiver
     pub fn new(
         inner_receiver: Box<dyn PacketReceiverTrait>,
-        link_conditioner_config: &LinkConditionerConfig,
+        link_conditioner: &LinkConditionerHandle,
     ) -> Self {
         ConditionedPacketReceiver {
             inner_receiver,
-            link_conditioner_config: link_conditioner_config.clone(),
-            time_queue: TimeQueue::new(),
+            link_conditioner: LinkConditioner::from_handle(
+                link_conditioner,
+                LinkDirection::Incoming,
+            ),
             last_payload: None,
+            outgoing_sender: None,
         }
     }
+
+    /// Flushes the delayed packets of the given ConditionedPacketSender each
+    /// time a packet is received, so they are sent even while the application
+    /// isn't sending anything itself
+    pub fn with_outgoing_sender(mut self, outgoing_sender: ConditionedPacketSender) -> Self {
+        self.outgoing_sender = Some(outgoing_sender);
+        self
+    }
 }
 
 impl PacketReceiverTrait for ConditionedPacketReceiver {
     fn receive(&mut self) -> Result<Option<&[u8]>, NaiaClientSocketError> {
+        if let Some(outgoing_sender) = &self.outgoing_sender {
+            outgoing_sender.flush();
+        }
+
         loop {
             match self.inner_receiver.receive() {
                 Ok(option) => match option {
@@ -37,11 +53,7 @@ impl PacketReceiverTrait for ConditionedPacketReceiver {
                         break;
                     }
                     Some(payload) => {
-                        link_condition_logic::process_packet(
-                            &self.link_conditioner_config,
-                            &mut self.time_queue,
-                            payload.into(),
-                        );
+                        self.link_conditioner.process_packet(payload.into());
                     }
                 },
                 Err(err) => {
@@ -50,8 +62,8 @@ impl PacketReceiverTrait for ConditionedPacketReceiver {
             }
         }
 
-        if self.time_queue.has_item() {
-            self.last_payload = Some(self.time_queue.pop_item().unwrap());
+        if self.link_conditioner.has_item() {
+            self.last_payload = Some(self.link_conditioner.pop_item().unwrap());
             return Ok(Some(self.last_payload.as_ref().unwrap()));
         } else {
             return Ok(None);
@@ -0,0 +1,53 @@
+use std::sync::{Arc, Mutex};
+
+use naia_socket_shared::{LinkConditioner, LinkConditionerHandle, LinkDirection};
+
+use super::{packet_sender::PacketSenderTrait, server_addr::ServerAddr};
+
+/// Handles sending messages to the Server for a given Client Socket, after
+/// delaying or dropping them according to the outgoing link conditions.
+/// Delayed packets are sent on later calls to `send()`, or to `receive()` on
+/// the Socket's PacketReceiver
+#[derive(Clone)]
+pub struct ConditionedPacketSender {
+    inner_sender: Box<dyn PacketSenderTrait>,
+    link_conditioner: Arc<Mutex<LinkConditioner<Box<[u8]>>>>,
+}
+
+impl ConditionedPacketSender {
+    /// Creates a new ConditionedPacketSender
+    pub fn new(
+        inner_sender: Box<dyn PacketSenderTrait>,
+        link_conditioner: &LinkConditionerHandle,
+    ) -> Self {
+        ConditionedPacketSender {
+            inner_sender,
+            link_conditioner: Arc::new(Mutex::new(LinkConditioner::from_handle(
+                link_conditioner,
+                LinkDirection::Outgoing,
+            ))),
+        }
+    }
+
+    /// Sends every delayed packet which is now due
+    pub fn flush(&self) {
+        let mut link_conditioner = self.link_conditioner.lock().unwrap();
+        while let Some(payload) = link_conditioner.pop_item() {
+            self.inner_sender.send(&payload);
+        }
+    }
+}
+
+impl PacketSenderTrait for ConditionedPacketSender {
+    fn send(&self, payload: &[u8]) {
+        self.link_conditioner
+            .lock()
+            .unwrap()
+            .process_packet(payload.into());
+        self.flush();
+    }
+
+    fn server_addr(&self) -> ServerAddr {
+        self.inner_sender.server_addr()
+    }
+}
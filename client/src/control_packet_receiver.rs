@@ -0,0 +1,183 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use naia_socket_shared::{ControlPacket, Instant, QualityEstimator, TimeSync, Timer, Timestamp};
+
+use super::{
+    error::NaiaClientSocketError, keepalive::Keepalive, packet_receiver::PacketReceiverTrait,
+    packet_sender::PacketSenderTrait, server_addr::ServerAddr,
+};
+
+// Probes which haven't been answered after this many more have been sent are
+// presumed lost
+const MAX_PENDING_PROBES: usize = 16;
+
+/// Used to receive packets from the Client Socket, while exchanging control
+/// packets with the Server to sync clocks, measure the connection's quality,
+/// & notice when the Server goes away. Control packets are sent on calls to
+/// `receive()`, & the replies are consumed rather than returned
+#[derive(Clone)]
+pub struct ControlPacketReceiver {
+    inner_receiver: Box<dyn PacketReceiverTrait>,
+    sender: Box<dyn PacketSenderTrait>,
+    time_sync: Option<TimeSyncProbes>,
+    quality: Option<QualityEstimator>,
+    keepalive: Option<Keepalive>,
+    last_payload: Option<Box<[u8]>>,
+}
+
+impl ControlPacketReceiver {
+    /// Creates a new ControlPacketReceiver, which sends control packets
+    /// through the given sender
+    pub fn new(
+        inner_receiver: Box<dyn PacketReceiverTrait>,
+        sender: Box<dyn PacketSenderTrait>,
+        time_sync: &Option<TimeSync>,
+        quality: &Option<QualityEstimator>,
+        keepalive: Option<Keepalive>,
+    ) -> Self {
+        ControlPacketReceiver {
+            inner_receiver,
+            sender,
+            time_sync: time_sync.as_ref().map(TimeSyncProbes::new),
+            quality: quality.clone(),
+            keepalive,
+            last_payload: None,
+        }
+    }
+
+    fn send_due(&self) {
+        if let Some(time_sync) = &self.time_sync {
+            if let Some(probe) = time_sync.next_probe() {
+                self.sender.send(&probe.to_bytes());
+            }
+        }
+        if let Some(quality) = &self.quality {
+            if let Some(ping) = quality.next_ping() {
+                self.sender.send(&ping.to_bytes());
+            }
+        }
+        if let Some(keepalive) = &self.keepalive {
+            if let Some(heartbeat) = keepalive.poll() {
+                self.sender.send(&heartbeat.to_bytes());
+            }
+        }
+    }
+
+    fn handle(&self, packet: ControlPacket) {
+        match packet {
+            ControlPacket::TimeReply { id, received, sent } => {
+                if let Some(time_sync) = &self.time_sync {
+                    time_sync.handle_reply(id, received, sent);
+                }
+            }
+            ControlPacket::Pong { id, pings_received } => {
+                if let Some(quality) = &self.quality {
+                    quality.record_pong(id, pings_received, &Instant::now());
+                }
+            }
+            ControlPacket::Heartbeat { epoch: Some(epoch) } => {
+                if let Some(keepalive) = &self.keepalive {
+                    keepalive.heard_epoch(epoch);
+                }
+            }
+            ControlPacket::Goodbye => {
+                if let Some(keepalive) = &self.keepalive {
+                    keepalive.goodbye();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl PacketReceiverTrait for ControlPacketReceiver {
+    fn receive(&mut self) -> Result<Option<&[u8]>, NaiaClientSocketError> {
+        self.send_due();
+
+        loop {
+            match self.inner_receiver.receive()? {
+                None => return Ok(None),
+                Some(payload) => {
+                    if let Some(keepalive) = &self.keepalive {
+                        keepalive.heard();
+                    }
+                    match ControlPacket::from_bytes(payload) {
+                        Some(packet) => self.handle(packet),
+                        None => {
+                            self.last_payload = Some(payload.into());
+                            return Ok(self.last_payload.as_deref());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get the Server's Socket address
+    fn server_addr(&self) -> ServerAddr {
+        self.inner_receiver.server_addr()
+    }
+}
+
+// Probes the Server's clock, feeding the replies to a TimeSync. Clones share
+// the same probes
+#[derive(Clone)]
+struct TimeSyncProbes {
+    time_sync: TimeSync,
+    probes: Arc<Mutex<Probes>>,
+}
+
+struct Probes {
+    timer: Timer,
+    next_id: u32,
+    pending: VecDeque<(u32, Instant)>,
+}
+
+impl TimeSyncProbes {
+    fn new(time_sync: &TimeSync) -> Self {
+        let mut timer =
+            Timer::with_clock(time_sync.config().probe_interval, time_sync.clock().clone());
+        timer.ring_manual();
+        TimeSyncProbes {
+            time_sync: time_sync.clone(),
+            probes: Arc::new(Mutex::new(Probes {
+                timer,
+                next_id: 0,
+                pending: VecDeque::new(),
+            })),
+        }
+    }
+
+    fn next_probe(&self) -> Option<ControlPacket> {
+        let mut probes = self.probes.lock().unwrap();
+        if !probes.timer.ringing() {
+            return None;
+        }
+        probes.timer.reset();
+
+        let id = probes.next_id;
+        probes.next_id = probes.next_id.wrapping_add(1);
+        probes.pending.push_back((id, self.time_sync.clock().now()));
+        if probes.pending.len() > MAX_PENDING_PROBES {
+            probes.pending.pop_front();
+        }
+        Some(ControlPacket::TimeProbe { id })
+    }
+
+    fn handle_reply(&self, id: u32, received: Timestamp, sent: Timestamp) {
+        let mut probes = self.probes.lock().unwrap();
+        if let Some(index) = probes
+            .pending
+            .iter()
+            .position(|(probe_id, _)| *probe_id == id)
+        {
+            let (_, sent_at) = probes.pending.remove(index).unwrap();
+            let received_at = self.time_sync.clock().now();
+            self.time_sync
+                .add_sample(&sent_at, &received_at, received, sent);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use super::client_event::ClientEvent;
+
+/// Used to receive events from the Client Socket. Clones share the same
+/// events, so each event is received once
+#[derive(Clone)]
+pub struct EventReceiver {
+    events: Arc<Mutex<VecDeque<ClientEvent>>>,
+}
+
+impl EventReceiver {
+    /// Creates a new EventReceiver
+    pub fn new() -> Self {
+        EventReceiver {
+            events: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Receives an event from the Client Socket, if any are waiting
+    pub fn receive(&mut self) -> Option<ClientEvent> {
+        self.events.lock().unwrap().pop_front()
+    }
+
+    pub(crate) fn push(&self, event: ClientEvent) {
+        self.events.lock().unwrap().push_back(event);
+    }
+}
+
+impl Default for EventReceiver {
+    fn default() -> Self {
+        EventReceiver::new()
+    }
+}
@@ -19,6 +19,7 @@ const naia_socket = {
         importObject.env.naia_free_object = function (js_object) { naia_socket.js_free_object(js_object); };
         importObject.env.naia_random = function () { return Math.random(); };
         importObject.env.naia_now = function () { return Date.now(); };
+        importObject.env.naia_instant_now = function () { return performance.now(); };
     },
 
     connect: function (server_socket_address, rtc_path) {
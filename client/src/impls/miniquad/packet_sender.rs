@@ -1,19 +1,21 @@
 use super::shared::{naia_create_u8_array, naia_send, SERVER_ADDR};
-use crate::ServerAddr;
+use crate::{packet_sender::PacketSenderTrait, ServerAddr};
 
 /// Handles sending messages to the Server for a given Client Socket
 #[derive(Clone)]
-pub struct PacketSender;
+pub struct PacketSenderImpl;
 
-impl PacketSender {
+impl PacketSenderImpl {
     /// Create a new PacketSender, if supplied with the RtcDataChannel and a
     /// reference to a list of dropped messages
     pub fn new() -> Self {
-        PacketSender
+        PacketSenderImpl
     }
+}
 
+impl PacketSenderTrait for PacketSenderImpl {
     /// Send a Packet to the Server
-    pub fn send(&self, payload: &[u8]) {
+    fn send(&self, payload: &[u8]) {
         unsafe {
             let ptr = payload.as_ptr();
             let len = payload.len();
@@ -23,7 +25,7 @@ impl PacketSender {
     }
 
     /// Get the Server's Socket address
-    pub fn server_addr(&self) -> ServerAddr {
+    fn server_addr(&self) -> ServerAddr {
         unsafe { SERVER_ADDR }
     }
 }
@@ -1,16 +1,14 @@
 use std::collections::VecDeque;
 
-use naia_socket_shared::{parse_server_url, SocketConfig};
-
-use crate::{
-    conditioned_packet_receiver::ConditionedPacketReceiver,
-    io::Io,
-    packet_receiver::{PacketReceiver, PacketReceiverTrait},
+use naia_socket_shared::{
+    parse_server_url, LinkConditionerHandle, QualityEstimator, SocketConfig, TimeSync,
 };
 
+use crate::{io::Io, keepalive::Keepalive, EventReceiver, PacketReceiver, PacketSender};
+
 use super::{
     packet_receiver::PacketReceiverImpl,
-    packet_sender::PacketSender,
+    packet_sender::PacketSenderImpl,
     shared::{naia_connect, JsObject, ERROR_QUEUE, MESSAGE_QUEUE},
 };
 
@@ -18,6 +16,10 @@ use super::{
 /// unreliable protocol
 pub struct Socket {
     config: SocketConfig,
+    link_conditioner: Option<LinkConditionerHandle>,
+    time_sync: Option<TimeSync>,
+    quality: Option<QualityEstimator>,
+    events: EventReceiver,
     io: Option<Io>,
 }
 
@@ -26,6 +28,13 @@ impl Socket {
     pub fn new(config: &SocketConfig) -> Self {
         Socket {
             config: config.clone(),
+            link_conditioner: config
+                .link_condition
+                .clone()
+                .map(LinkConditionerHandle::new),
+            time_sync: config.time_sync.clone().map(TimeSync::new),
+            quality: config.connection_quality.clone().map(QualityEstimator::new),
+            events: EventReceiver::new(),
             io: None,
         }
     }
@@ -43,22 +52,46 @@ impl Socket {
             );
         }
 
-        let conditioner_config = self.config.link_condition.clone();
+        self.io = Some(Io::new(
+            &self.link_conditioner,
+            &self.time_sync,
+            &self.quality,
+            self.keepalive(),
+            Box::new(PacketSenderImpl::new()),
+            Box::new(PacketReceiverImpl::new()),
+        ));
+    }
+
+    /// Gets a LinkConditionerHandle which can be used to change the simulated
+    /// network conditions while the Socket runs, & to read what they have
+    /// done. Returns None if the Socket was created without a link condition
+    pub fn link_conditioner(&self) -> Option<LinkConditionerHandle> {
+        self.link_conditioner.clone()
+    }
 
-        let sender = PacketSender::new();
-        let receiver: Box<dyn PacketReceiverTrait> = {
-            let inner_receiver = Box::new(PacketReceiverImpl::new());
-            if let Some(config) = &conditioner_config {
-                Box::new(ConditionedPacketReceiver::new(inner_receiver, config))
-            } else {
-                inner_receiver
+    /// Gets the TimeSync which estimates the Server's clock, e.g.
+    /// `socket.time_sync().and_then(|time_sync| time_sync.estimated_server_time())`.
+    /// Returns None if the Socket was created without a time sync config
+    pub fn time_sync(&self) -> Option<TimeSync> {
+        self.time_sync.clone()
+    }
+
+    /// Disconnects from the Server. If the Socket was created with a
+    /// keepalive config, a goodbye is sent first so the Server learns of it
+    /// immediately
+    pub fn disconnect(&mut self) {
+        if let Some(io) = self.io.take() {
+            if self.config.keepalive.is_some() {
+                io.send_goodbye();
             }
-        };
+        }
+    }
 
-        self.io = Some(Io {
-            packet_sender: sender,
-            packet_receiver: PacketReceiver::new(receiver),
-        });
+    /// Gets an EventReceiver which can be used to receive events from the
+    /// Socket, such as the Server disconnecting. Events are raised during
+    /// calls to `PacketReceiver.receive()`
+    pub fn event_receiver(&self) -> EventReceiver {
+        self.events.clone()
     }
 
     /// Gets a PacketSender which can be used to send packets through the Socket
@@ -81,4 +114,13 @@ impl Socket {
             .packet_receiver
             .clone();
     }
+
+    // Creates a Keepalive for a new connection, if the Socket was created
+    // with a keepalive config
+    fn keepalive(&self) -> Option<Keepalive> {
+        self.config
+            .keepalive
+            .clone()
+            .map(|config| Keepalive::new(config, &self.events))
+    }
 }
@@ -3,27 +3,29 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use crate::server_addr::ServerAddr;
+use crate::{packet_sender::PacketSenderTrait, server_addr::ServerAddr};
 
 /// Handles sending messages to the Server for a given Client Socket
 #[derive(Clone)]
-pub struct PacketSender {
+pub struct PacketSenderImpl {
     server_addr: SocketAddr,
     local_socket: Arc<Mutex<UdpSocket>>,
 }
 
-impl PacketSender {
+impl PacketSenderImpl {
     /// Create a new PacketSender, if supplied with the Server's address & a
     /// reference back to the parent Socket
     pub fn new(server_addr: SocketAddr, local_socket: Arc<Mutex<UdpSocket>>) -> Self {
-        PacketSender {
+        PacketSenderImpl {
             server_addr,
             local_socket,
         }
     }
+}
 
+impl PacketSenderTrait for PacketSenderImpl {
     /// Send a Packet to the Server
-    pub fn send(&self, payload: &[u8]) {
+    fn send(&self, payload: &[u8]) {
         //send it
         if let Err(_) = self
             .local_socket
@@ -37,7 +39,7 @@ impl PacketSender {
     }
 
     /// Get the Server's Socket address
-    pub fn server_addr(&self) -> ServerAddr {
+    fn server_addr(&self) -> ServerAddr {
         ServerAddr::Found(self.server_addr)
     }
 }
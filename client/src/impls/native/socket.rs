@@ -7,20 +7,28 @@ use std::{
 
 use log::info;
 
-use naia_socket_shared::{parse_server_url, url_to_socket_addr, SocketConfig};
+use naia_socket_shared::{
+    parse_server_url, url_to_socket_addr, KeepaliveConfig, LinkConditionerHandle, LoopbackHub,
+    QualityEstimator, SocketConfig, TimeSync,
+};
 
 use crate::{
-    conditioned_packet_receiver::ConditionedPacketReceiver,
     io::Io,
-    packet_receiver::{PacketReceiver, PacketReceiverTrait},
+    keepalive::Keepalive,
+    loopback::{LoopbackPacketReceiver, LoopbackPacketSender},
+    EventReceiver, PacketReceiver, PacketSender,
 };
 
-use super::{packet_receiver::PacketReceiverImpl, packet_sender::PacketSender};
+use super::{packet_receiver::PacketReceiverImpl, packet_sender::PacketSenderImpl};
 
 /// A client-side socket which communicates with an underlying unordered &
 /// unreliable protocol
 pub struct Socket {
-    config: SocketConfig,
+    link_conditioner: Option<LinkConditionerHandle>,
+    time_sync: Option<TimeSync>,
+    quality: Option<QualityEstimator>,
+    keepalive_config: Option<KeepaliveConfig>,
+    events: EventReceiver,
     io: Option<Io>,
 }
 
@@ -28,7 +36,14 @@ impl Socket {
     /// Create a new Socket
     pub fn new(config: &SocketConfig) -> Self {
         Socket {
-            config: config.clone(),
+            link_conditioner: config
+                .link_condition
+                .clone()
+                .map(LinkConditionerHandle::new),
+            time_sync: config.time_sync.clone().map(TimeSync::new),
+            quality: config.connection_quality.clone().map(QualityEstimator::new),
+            keepalive_config: config.keepalive.clone(),
+            events: EventReceiver::new(),
             io: None,
         }
     }
@@ -54,26 +69,72 @@ impl Socket {
             .expect("can't set socket to non-blocking!");
         let local_addr = socket.as_ref().lock().unwrap().local_addr().unwrap();
 
-        let packet_sender = PacketSender::new(server_socket_addr, socket.clone());
+        let inner_sender = Box::new(PacketSenderImpl::new(server_socket_addr, socket.clone()));
+        let inner_receiver = Box::new(PacketReceiverImpl::new(server_socket_addr, socket.clone()));
 
-        let conditioner_config = self.config.link_condition.clone();
+        info!("UDP client listening on socket: {}", local_addr);
 
-        let receiver: Box<dyn PacketReceiverTrait> = {
-            let inner_receiver =
-                Box::new(PacketReceiverImpl::new(server_socket_addr, socket.clone()));
-            if let Some(config) = &conditioner_config {
-                Box::new(ConditionedPacketReceiver::new(inner_receiver, config))
-            } else {
-                inner_receiver
-            }
-        };
+        self.io = Some(Io::new(
+            &self.link_conditioner,
+            &self.time_sync,
+            &self.quality,
+            self.keepalive(),
+            inner_sender,
+            inner_receiver,
+        ));
+    }
 
-        info!("UDP client listening on socket: {}", local_addr);
+    /// Connects to a Server Socket in the same process through the given
+    /// LoopbackHub, rather than over the network. Each Socket connected to
+    /// the hub is given its own fake address
+    pub fn connect_loopback(&mut self, hub: &LoopbackHub) {
+        if self.io.is_some() {
+            panic!("Socket already listening!");
+        }
+
+        let client = hub.add_client();
+        info!("Loopback client connected as: {}", client.address());
+
+        self.io = Some(Io::new(
+            &self.link_conditioner,
+            &self.time_sync,
+            &self.quality,
+            self.keepalive(),
+            Box::new(LoopbackPacketSender::new(client.clone())),
+            Box::new(LoopbackPacketReceiver::new(client)),
+        ));
+    }
 
-        self.io = Some(Io {
-            packet_sender: packet_sender.clone(),
-            packet_receiver: PacketReceiver::new(receiver),
-        });
+    /// Gets a LinkConditionerHandle which can be used to change the simulated
+    /// network conditions while the Socket runs, & to read what they have
+    /// done. Returns None if the Socket was created without a link condition
+    pub fn link_conditioner(&self) -> Option<LinkConditionerHandle> {
+        self.link_conditioner.clone()
+    }
+
+    /// Gets the TimeSync which estimates the Server's clock, e.g.
+    /// `socket.time_sync().and_then(|time_sync| time_sync.estimated_server_time())`.
+    /// Returns None if the Socket was created without a time sync config
+    pub fn time_sync(&self) -> Option<TimeSync> {
+        self.time_sync.clone()
+    }
+
+    /// Disconnects from the Server. If the Socket was created with a
+    /// keepalive config, a goodbye is sent first so the Server learns of it
+    /// immediately
+    pub fn disconnect(&mut self) {
+        if let Some(io) = self.io.take() {
+            if self.keepalive_config.is_some() {
+                io.send_goodbye();
+            }
+        }
+    }
+
+    /// Gets an EventReceiver which can be used to receive events from the
+    /// Socket, such as the Server disconnecting. Events are raised during
+    /// calls to `PacketReceiver.receive()`
+    pub fn event_receiver(&self) -> EventReceiver {
+        self.events.clone()
     }
 
     /// Gets a PacketSender which can be used to send packets through the Socket
@@ -96,6 +157,14 @@ impl Socket {
             .packet_receiver
             .clone();
     }
+
+    // Creates a Keepalive for a new connection, if the Socket was created
+    // with a keepalive config
+    fn keepalive(&self) -> Option<Keepalive> {
+        self.keepalive_config
+            .clone()
+            .map(|config| Keepalive::new(config, &self.events))
+    }
 }
 
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
@@ -4,3 +4,4 @@ mod webrtc_internal;
 pub mod packet_receiver;
 pub mod packet_sender;
 pub mod socket;
+pub mod stats;
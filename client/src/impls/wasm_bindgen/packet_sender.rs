@@ -3,17 +3,17 @@ use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 use web_sys::RtcDataChannel;
 
 use super::addr_cell::AddrCell;
-use crate::server_addr::ServerAddr;
+use crate::{packet_sender::PacketSenderTrait, server_addr::ServerAddr};
 
 /// Handles sending messages to the Server for a given Client Socket
 #[derive(Clone)]
-pub struct PacketSender {
+pub struct PacketSenderImpl {
     data_channel: RtcDataChannel,
     dropped_outgoing_messages: Rc<RefCell<VecDeque<Box<[u8]>>>>,
     server_addr: AddrCell,
 }
 
-impl PacketSender {
+impl PacketSenderImpl {
     /// Create a new PacketSender, if supplied with the RtcDataChannel and a
     /// reference to a list of dropped messages
     pub fn new(
@@ -21,15 +21,23 @@ impl PacketSender {
         dropped_outgoing_messages: Rc<RefCell<VecDeque<Box<[u8]>>>>,
         server_addr: AddrCell,
     ) -> Self {
-        PacketSender {
+        PacketSenderImpl {
             data_channel,
             dropped_outgoing_messages,
             server_addr,
         }
     }
 
+    fn resend_dropped_messages(&self) {
+        if let Some(dropped_packet) = self.dropped_outgoing_messages.borrow_mut().pop_front() {
+            self.send(&dropped_packet);
+        }
+    }
+}
+
+impl PacketSenderTrait for PacketSenderImpl {
     /// Send a Packet to the Server
-    pub fn send(&self, payload: &[u8]) {
+    fn send(&self, payload: &[u8]) {
         self.resend_dropped_messages();
 
         if let Err(err) = self.data_channel.send_with_u8_array(payload) {
@@ -41,17 +49,11 @@ impl PacketSender {
         }
     }
 
-    fn resend_dropped_messages(&self) {
-        if let Some(dropped_packet) = self.dropped_outgoing_messages.borrow_mut().pop_front() {
-            self.send(&dropped_packet);
-        }
-    }
-
     /// Get the Server's Socket address
-    pub fn server_addr(&self) -> ServerAddr {
+    fn server_addr(&self) -> ServerAddr {
         self.server_addr.get()
     }
 }
 
-unsafe impl Send for PacketSender {}
-unsafe impl Sync for PacketSender {}
+unsafe impl Send for PacketSenderImpl {}
+unsafe impl Sync for PacketSenderImpl {}
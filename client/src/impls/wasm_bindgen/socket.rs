@@ -2,23 +2,32 @@ extern crate log;
 
 use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 
-use naia_socket_shared::{parse_server_url, SocketConfig};
+use naia_socket_shared::{
+    parse_server_url, LinkConditionerHandle, QualityEstimator, SocketConfig, TimeSync,
+};
 
 use crate::{
-    conditioned_packet_receiver::ConditionedPacketReceiver,
-    io::Io,
-    packet_receiver::{PacketReceiver, PacketReceiverTrait},
+    io::Io, keepalive::Keepalive, webrtc_stats::WebRtcStats, EventReceiver, PacketReceiver,
+    PacketSender,
 };
 
 use super::{
-    addr_cell::AddrCell, packet_receiver::PacketReceiverImpl, packet_sender::PacketSender,
-    webrtc_internal::webrtc_initialize,
+    addr_cell::AddrCell,
+    packet_receiver::PacketReceiverImpl,
+    packet_sender::PacketSenderImpl,
+    webrtc_internal::{webrtc_initialize, WebRtcConnection},
 };
 
 /// A client-side socket which communicates with an underlying unordered &
 /// unreliable protocol
 pub struct Socket {
     config: SocketConfig,
+    link_conditioner: Option<LinkConditionerHandle>,
+    time_sync: Option<TimeSync>,
+    quality: Option<QualityEstimator>,
+    events: EventReceiver,
+    stats: Rc<RefCell<Option<WebRtcStats>>>,
+    connection: Option<WebRtcConnection>,
     io: Option<Io>,
 }
 
@@ -27,6 +36,15 @@ impl Socket {
     pub fn new(config: &SocketConfig) -> Self {
         Socket {
             config: config.clone(),
+            link_conditioner: config
+                .link_condition
+                .clone()
+                .map(LinkConditionerHandle::new),
+            time_sync: config.time_sync.clone().map(TimeSync::new),
+            quality: config.connection_quality.clone().map(QualityEstimator::new),
+            events: EventReceiver::new(),
+            stats: Rc::new(RefCell::new(None)),
+            connection: None,
             io: None,
         }
     }
@@ -41,35 +59,78 @@ impl Socket {
 
         let addr_cell = AddrCell::new();
         let message_queue = Rc::new(RefCell::new(VecDeque::new()));
-        let data_channel = webrtc_initialize(
+        let connection = webrtc_initialize(
             format!("{}{}", server_url, self.config.rtc_endpoint_path.clone()),
             message_queue.clone(),
             addr_cell.clone(),
+            self.stats.clone(),
+            self.quality.clone(),
         );
 
         let dropped_outgoing_messages = Rc::new(RefCell::new(VecDeque::new()));
 
-        let packet_sender = PacketSender::new(
-            data_channel.clone(),
+        let inner_sender = Box::new(PacketSenderImpl::new(
+            connection.data_channel().clone(),
             dropped_outgoing_messages.clone(),
             addr_cell.clone(),
-        );
-        let packet_receiver = PacketReceiverImpl::new(message_queue.clone(), addr_cell.clone());
-
-        let sender = packet_sender.clone();
-        let receiver: Box<dyn PacketReceiverTrait> = {
-            let inner_receiver = Box::new(packet_receiver.clone());
-            if let Some(config) = &self.config.link_condition {
-                Box::new(ConditionedPacketReceiver::new(inner_receiver, config))
-            } else {
-                inner_receiver
+        ));
+        let inner_receiver = Box::new(PacketReceiverImpl::new(
+            message_queue.clone(),
+            addr_cell.clone(),
+        ));
+
+        self.io = Some(Io::new(
+            &self.link_conditioner,
+            &self.time_sync,
+            &self.quality,
+            self.keepalive(),
+            inner_sender,
+            inner_receiver,
+        ));
+        self.connection = Some(connection);
+    }
+
+    /// Gets a LinkConditionerHandle which can be used to change the simulated
+    /// network conditions while the Socket runs, & to read what they have
+    /// done. Returns None if the Socket was created without a link condition
+    pub fn link_conditioner(&self) -> Option<LinkConditionerHandle> {
+        self.link_conditioner.clone()
+    }
+
+    /// Gets the TimeSync which estimates the Server's clock, e.g.
+    /// `socket.time_sync().and_then(|time_sync| time_sync.estimated_server_time())`.
+    /// Returns None if the Socket was created without a time sync config
+    pub fn time_sync(&self) -> Option<TimeSync> {
+        self.time_sync.clone()
+    }
+
+    /// Gets the browser's latest statistics for the WebRTC connection, which
+    /// are read once every ping interval, or every second without a
+    /// connection quality config, until the Socket disconnects. Returns None
+    /// until the first are read
+    pub fn stats(&self) -> Option<WebRtcStats> {
+        self.stats.borrow().clone()
+    }
+
+    /// Disconnects from the Server. If the Socket was created with a
+    /// keepalive config, a goodbye is sent first so the Server learns of it
+    /// immediately. The browser's WebRTC connection is then closed
+    pub fn disconnect(&mut self) {
+        if let Some(io) = self.io.take() {
+            if self.config.keepalive.is_some() {
+                io.send_goodbye();
             }
-        };
+        }
+        if let Some(connection) = self.connection.take() {
+            connection.close();
+        }
+    }
 
-        self.io = Some(Io {
-            packet_sender: sender,
-            packet_receiver: PacketReceiver::new(receiver),
-        });
+    /// Gets an EventReceiver which can be used to receive events from the
+    /// Socket, such as the Server disconnecting. Events are raised during
+    /// calls to `PacketReceiver.receive()`
+    pub fn event_receiver(&self) -> EventReceiver {
+        self.events.clone()
     }
 
     /// Gets a PacketSender which can be used to send packets through the Socket
@@ -92,6 +153,15 @@ impl Socket {
             .packet_receiver
             .clone();
     }
+
+    // Creates a Keepalive for a new connection, if the Socket was created
+    // with a keepalive config
+    fn keepalive(&self) -> Option<Keepalive> {
+        self.config
+            .keepalive
+            .clone()
+            .map(|config| Keepalive::new(config, &self.events))
+    }
 }
 
 unsafe impl Send for Socket {}
@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+use js_sys::{Map, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::webrtc_stats::{StatsObject, WebRtcStats};
+
+impl StatsObject for JsValue {
+    fn string(&self, name: &str) -> Option<String> {
+        field(self, name)?.as_string()
+    }
+
+    fn number(&self, name: &str) -> Option<f64> {
+        field(self, name)?.as_f64()
+    }
+
+    fn boolean(&self, name: &str) -> bool {
+        field(self, name)
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false)
+    }
+}
+
+/// Reads the stats from an RTCStatsReport, given the data channel's
+/// bufferedAmount, which the report doesn't include
+pub(crate) fn from_report(report: JsValue, buffered_amount: u32) -> WebRtcStats {
+    // The report maps each stats object's id to the object
+    let mut entries = HashMap::new();
+    report
+        .unchecked_into::<Map>()
+        .for_each(&mut |stats: JsValue, id: JsValue| {
+            if let Some(id) = id.as_string() {
+                entries.insert(id, stats);
+            }
+        });
+    WebRtcStats::from_entries(&entries, buffered_amount)
+}
+
+fn field(stats: &JsValue, name: &str) -> Option<JsValue> {
+    Reflect::get(stats, &JsValue::from(name)).ok()
+}
@@ -1,9 +1,10 @@
 extern crate log;
 
-use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc, time::Duration};
 
 use js_sys::{Array, Object, Reflect};
 use log::info;
+use naia_socket_shared::QualityEstimator;
 use tinyjson::JsonValue;
 use wasm_bindgen::{prelude::*, JsCast, JsValue};
 use web_sys::{
@@ -12,7 +13,32 @@ use web_sys::{
     RtcSessionDescriptionInit, XmlHttpRequest,
 };
 
-use super::addr_cell::AddrCell;
+use crate::webrtc_stats::WebRtcStats;
+
+use super::{addr_cell::AddrCell, stats::from_report};
+
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[wasm_bindgen]
+extern "C" {
+    // The global object, a Window on a page or a WorkerGlobalScope in a Web
+    // Worker, both of which have timers
+    type Global;
+
+    #[wasm_bindgen(method, catch, js_name = setInterval)]
+    fn set_interval(
+        this: &Global,
+        handler: &js_sys::Function,
+        timeout: i32,
+    ) -> Result<i32, JsValue>;
+
+    #[wasm_bindgen(method, js_name = clearInterval)]
+    fn clear_interval(this: &Global, handle: i32);
+}
+
+fn global() -> Global {
+    js_sys::global().unchecked_into()
+}
 
 #[derive(Clone)]
 pub struct SessionAnswer {
@@ -60,7 +86,9 @@ pub fn webrtc_initialize(
     server_url_str: String,
     msg_queue: Rc<RefCell<VecDeque<Box<[u8]>>>>,
     addr_cell: AddrCell,
-) -> RtcDataChannel {
+    stats: Rc<RefCell<Option<WebRtcStats>>>,
+    quality: Option<QualityEstimator>,
+) -> WebRtcConnection {
     // Set up Ice Servers
     let ice_server_config_urls = Array::new();
     ice_server_config_urls.push(&JsValue::from("stun:stun.l.google.com:19302"));
@@ -90,6 +118,8 @@ pub fn webrtc_initialize(
                 peer.create_data_channel_with_data_channel_dict("data", &data_channel_config);
             channel.set_binary_type(RtcDataChannelType::Arraybuffer);
 
+            let stats_poller = StatsPoller::start(peer.clone(), channel.clone(), stats, quality);
+
             let channel_2 = channel.clone();
             let msg_queue_2 = msg_queue.clone();
             let channel_onopen_func: Box<dyn FnMut(JsValue)> = Box::new(move |_| {
@@ -241,7 +271,11 @@ pub fn webrtc_initialize(
             peer_offer_callback.forget();
             peer_error_callback.forget();
 
-            return channel;
+            return WebRtcConnection {
+                peer,
+                channel,
+                _stats_poller: stats_poller,
+            };
         }
         Err(err) => {
             info!("Error creating new RtcPeerConnection. Error: {:?}", err);
@@ -249,3 +283,117 @@ pub fn webrtc_initialize(
         }
     }
 }
+
+/// The browser's connection to the Server, whose statistics are polled until
+/// it is closed or dropped
+pub struct WebRtcConnection {
+    peer: RtcPeerConnection,
+    channel: RtcDataChannel,
+    _stats_poller: StatsPoller,
+}
+
+impl WebRtcConnection {
+    /// Gets the data channel packets are sent & received through
+    pub fn data_channel(&self) -> &RtcDataChannel {
+        &self.channel
+    }
+
+    /// Closes the data channel, & then the peer connection once the channel
+    /// has closed, so packets already sent are flushed rather than discarded
+    pub fn close(self) {
+        let peer = self.peer;
+        let onclose_func: Box<dyn FnMut(JsValue)> = Box::new(move |_: JsValue| {
+            peer.close();
+        });
+        let onclose_callback = Closure::wrap(onclose_func);
+        self.channel
+            .set_onclose(Some(onclose_callback.as_ref().unchecked_ref()));
+        onclose_callback.forget();
+        self.channel.close();
+    }
+}
+
+/// Reads the browser's statistics for the connection once every ping
+/// interval, or every second without pings, until dropped. The RTT of the
+/// selected ICE candidate pair is used to cross-check the RTT measured by pings
+struct StatsPoller {
+    interval_id: Option<i32>,
+    _interval_callback: Closure<dyn FnMut()>,
+    stats_callback: Rc<Closure<dyn FnMut(JsValue)>>,
+    target: Rc<RefCell<Option<StatsTarget>>>,
+}
+
+// Where the stats are read from & written to
+struct StatsTarget {
+    channel: RtcDataChannel,
+    stats: Rc<RefCell<Option<WebRtcStats>>>,
+    quality: Option<QualityEstimator>,
+}
+
+impl StatsPoller {
+    fn start(
+        peer: RtcPeerConnection,
+        channel: RtcDataChannel,
+        stats: Rc<RefCell<Option<WebRtcStats>>>,
+        quality: Option<QualityEstimator>,
+    ) -> Self {
+        let interval = match &quality {
+            Some(quality) => quality.config().ping_interval,
+            None => STATS_INTERVAL,
+        };
+        let target = Rc::new(RefCell::new(Some(StatsTarget {
+            channel,
+            stats,
+            quality,
+        })));
+
+        let target_2 = target.clone();
+        let stats_func: Box<dyn FnMut(JsValue)> = Box::new(move |report: JsValue| {
+            if let Some(target) = &*target_2.borrow() {
+                let report = from_report(report, target.channel.buffered_amount());
+                if let Some(quality) = &target.quality {
+                    quality.set_transport_rtt(report.current_rtt);
+                }
+                *target.stats.borrow_mut() = Some(report);
+            }
+        });
+        let stats_callback = Rc::new(Closure::wrap(stats_func));
+
+        let stats_callback_2 = stats_callback.clone();
+        let interval_func: Box<dyn FnMut()> = Box::new(move || {
+            peer.get_stats().then(&stats_callback_2);
+        });
+        let interval_callback = Closure::wrap(interval_func);
+        let interval_id = global()
+            .set_interval(
+                interval_callback.as_ref().unchecked_ref(),
+                interval.as_millis() as i32,
+            )
+            .map_err(|err| {
+                info!(
+                    "WebSys, can't poll connection stats. Original Error: {:?}",
+                    err
+                )
+            })
+            .ok();
+
+        StatsPoller {
+            interval_id,
+            _interval_callback: interval_callback,
+            stats_callback,
+            target,
+        }
+    }
+}
+
+impl Drop for StatsPoller {
+    fn drop(&mut self) {
+        if let Some(interval_id) = self.interval_id {
+            global().clear_interval(interval_id);
+        }
+        // A stats request may still be pending, so its callback is leaked
+        // rather than freed, but it no longer holds on to the connection
+        self.target.borrow_mut().take();
+        std::mem::forget(self.stats_callback.clone());
+    }
+}
@@ -1,4 +1,12 @@
-use crate::{PacketReceiver, PacketSender};
+use naia_socket_shared::{ControlPacket, LinkConditionerHandle, QualityEstimator, TimeSync};
+
+use crate::{
+    conditioned_packet_receiver::ConditionedPacketReceiver,
+    conditioned_packet_sender::ConditionedPacketSender,
+    control_packet_receiver::ControlPacketReceiver, keepalive::Keepalive,
+    packet_receiver::PacketReceiverTrait, packet_sender::PacketSenderTrait, PacketReceiver,
+    PacketSender,
+};
 
 /// Contains internal socket packet sender/receiver
 pub(crate) struct Io {
@@ -6,4 +14,57 @@ pub(crate) struct Io {
     pub packet_sender: PacketSender,
     /// Used to receive packets from the socket
     pub packet_receiver: PacketReceiver,
+    // The platform's sender, which is never link conditioned
+    inner_sender: Box<dyn PacketSenderTrait>,
+}
+
+impl Io {
+    /// Wraps the platform's sender & receiver, conditioning them if a
+    /// LinkConditionerHandle is supplied, probing the Server's clock if a
+    /// TimeSync is supplied, pinging the Server if a QualityEstimator is
+    /// supplied, & sending heartbeats if a Keepalive is supplied
+    pub fn new(
+        link_conditioner: &Option<LinkConditionerHandle>,
+        time_sync: &Option<TimeSync>,
+        quality: &Option<QualityEstimator>,
+        keepalive: Option<Keepalive>,
+        inner_sender: Box<dyn PacketSenderTrait>,
+        inner_receiver: Box<dyn PacketReceiverTrait>,
+    ) -> Self {
+        let (sender, receiver): (Box<dyn PacketSenderTrait>, Box<dyn PacketReceiverTrait>) =
+            match link_conditioner {
+                Some(handle) => {
+                    let sender = ConditionedPacketSender::new(inner_sender.clone(), handle);
+                    let receiver = ConditionedPacketReceiver::new(inner_receiver, handle)
+                        .with_outgoing_sender(sender.clone());
+                    (Box::new(sender), Box::new(receiver))
+                }
+                None => (inner_sender.clone(), inner_receiver),
+            };
+        let receiver: Box<dyn PacketReceiverTrait> =
+            if time_sync.is_some() || quality.is_some() || keepalive.is_some() {
+                Box::new(ControlPacketReceiver::new(
+                    receiver,
+                    sender.clone(),
+                    time_sync,
+                    quality,
+                    keepalive,
+                ))
+            } else {
+                receiver
+            };
+
+        Io {
+            packet_sender: PacketSender::new(sender),
+            packet_receiver: PacketReceiver::new(receiver).with_quality(quality.clone()),
+            inner_sender,
+        }
+    }
+
+    /// Tells the Server the Socket is disconnecting. The goodbye bypasses the
+    /// link conditioner, which would otherwise be able to hold it until after
+    /// the Socket is gone
+    pub fn send_goodbye(&self) {
+        self.inner_sender.send(&ControlPacket::Goodbye.to_bytes());
+    }
 }
@@ -0,0 +1,83 @@
+use std::sync::{Arc, Mutex};
+
+use naia_socket_shared::{ControlPacket, DisconnectReason, Instant, KeepaliveConfig, Timer};
+
+use super::{client_event::ClientEvent, event_receiver::EventReceiver};
+
+/// Sends heartbeats to the Server, & raises events when the Server goes quiet,
+/// says goodbye, or restarts. Clones share the same state
+#[derive(Clone)]
+pub struct Keepalive {
+    config: KeepaliveConfig,
+    state: Arc<Mutex<KeepaliveState>>,
+    events: EventReceiver,
+}
+
+struct KeepaliveState {
+    heartbeat_timer: Timer,
+    last_heard: Instant,
+    disconnected: bool,
+    epoch: Option<u64>,
+}
+
+impl Keepalive {
+    /// Creates a new Keepalive, which counts the idle timeout from now
+    pub fn new(config: KeepaliveConfig, events: &EventReceiver) -> Self {
+        let mut heartbeat_timer = Timer::new(config.heartbeat_interval);
+        heartbeat_timer.ring_manual();
+        Keepalive {
+            config,
+            state: Arc::new(Mutex::new(KeepaliveState {
+                heartbeat_timer,
+                last_heard: Instant::now(),
+                disconnected: false,
+                epoch: None,
+            })),
+            events: events.clone(),
+        }
+    }
+
+    /// Returns a heartbeat to send to the Server if one is due, & raises a
+    /// disconnect event if the Server has been quiet for the idle timeout
+    pub fn poll(&self) -> Option<ControlPacket> {
+        let mut state = self.state.lock().unwrap();
+        if !state.disconnected && state.last_heard.elapsed() > self.config.idle_timeout {
+            state.disconnected = true;
+            self.events
+                .push(ClientEvent::Disconnected(DisconnectReason::TimedOut));
+        }
+
+        if !state.heartbeat_timer.ringing() {
+            return None;
+        }
+        state.heartbeat_timer.reset();
+        Some(ControlPacket::Heartbeat { epoch: None })
+    }
+
+    /// Records that a packet was heard from the Server
+    pub fn heard(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.last_heard = Instant::now();
+        state.disconnected = false;
+    }
+
+    /// Records the Server's epoch, raising an event if it has changed
+    pub fn heard_epoch(&self, epoch: u64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(last_epoch) = state.epoch.replace(epoch) {
+            if last_epoch != epoch {
+                self.events.push(ClientEvent::ServerRestarted);
+            }
+        }
+    }
+
+    /// Records that the Server said goodbye
+    pub fn goodbye(&self) {
+        let mut state = self.state.lock().unwrap();
+        if !state.disconnected {
+            state.disconnected = true;
+            self.events
+                .push(ClientEvent::Disconnected(DisconnectReason::Goodbye));
+        }
+    }
+}
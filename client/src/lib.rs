@@ -17,23 +17,42 @@ extern crate cfg_if;
 cfg_if! {
     if #[cfg(target_arch = "wasm32")] {
         mod wasm_utils;
-    } else {}
+    } else {
+        mod loopback;
+    }
 }
 
+mod client_event;
 mod conditioned_packet_receiver;
+mod conditioned_packet_sender;
+mod control_packet_receiver;
 mod error;
+mod event_receiver;
 mod impls;
 mod io;
+mod keepalive;
 mod packet_receiver;
+mod packet_sender;
 mod server_addr;
+#[cfg(any(test, all(target_arch = "wasm32", feature = "wbindgen")))]
+mod webrtc_stats;
 
 pub use naia_socket_shared as shared;
 
+pub use client_event::ClientEvent;
 pub use error::NaiaClientSocketError;
-pub use impls::{PacketSender, Socket};
+pub use event_receiver::EventReceiver;
+pub use impls::Socket;
 pub use packet_receiver::PacketReceiver;
+pub use packet_sender::PacketSender;
 pub use server_addr::ServerAddr;
 
+cfg_if! {
+    if #[cfg(all(target_arch = "wasm32", feature = "wbindgen"))] {
+        pub use webrtc_stats::{CandidatePairStats, CandidateStats, WebRtcStats};
+    }
+}
+
 cfg_if! {
     if #[cfg(all(target_arch = "wasm32", feature = "wbindgen", feature = "mquad"))]
     {
@@ -0,0 +1,57 @@
+use naia_socket_shared::LoopbackClient;
+
+use super::{
+    error::NaiaClientSocketError, packet_receiver::PacketReceiverTrait,
+    packet_sender::PacketSenderTrait, server_addr::ServerAddr,
+};
+
+/// Handles sending messages to the Server through a LoopbackHub
+#[derive(Clone)]
+pub struct LoopbackPacketSender {
+    client: LoopbackClient,
+}
+
+impl LoopbackPacketSender {
+    /// Create a new LoopbackPacketSender for the given end of a LoopbackHub
+    pub fn new(client: LoopbackClient) -> Self {
+        LoopbackPacketSender { client }
+    }
+}
+
+impl PacketSenderTrait for LoopbackPacketSender {
+    fn send(&self, payload: &[u8]) {
+        self.client.send(payload);
+    }
+
+    fn server_addr(&self) -> ServerAddr {
+        ServerAddr::Found(self.client.server_addr())
+    }
+}
+
+/// Handles receiving messages from the Server through a LoopbackHub
+#[derive(Clone)]
+pub struct LoopbackPacketReceiver {
+    client: LoopbackClient,
+    last_payload: Option<Box<[u8]>>,
+}
+
+impl LoopbackPacketReceiver {
+    /// Create a new LoopbackPacketReceiver for the given end of a LoopbackHub
+    pub fn new(client: LoopbackClient) -> Self {
+        LoopbackPacketReceiver {
+            client,
+            last_payload: None,
+        }
+    }
+}
+
+impl PacketReceiverTrait for LoopbackPacketReceiver {
+    fn receive(&mut self) -> Result<Option<&[u8]>, NaiaClientSocketError> {
+        self.last_payload = self.client.receive();
+        Ok(self.last_payload.as_deref())
+    }
+
+    fn server_addr(&self) -> ServerAddr {
+        ServerAddr::Found(self.client.server_addr())
+    }
+}
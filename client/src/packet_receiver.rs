@@ -1,3 +1,5 @@
+use naia_socket_shared::{ConnectionQuality, QualityEstimator};
+
 use super::{error::NaiaClientSocketError, server_addr::ServerAddr};
 
 // Impl
@@ -6,12 +8,21 @@ use super::{error::NaiaClientSocketError, server_addr::ServerAddr};
 #[derive(Clone)]
 pub struct PacketReceiver {
     inner: Box<dyn PacketReceiverTrait>,
+    quality: Option<QualityEstimator>,
 }
 
 impl PacketReceiver {
     /// Create a new PacketReceiver
     pub fn new(inner: Box<dyn PacketReceiverTrait>) -> Self {
-        PacketReceiver { inner }
+        PacketReceiver {
+            inner,
+            quality: None,
+        }
+    }
+
+    pub(crate) fn with_quality(mut self, quality: Option<QualityEstimator>) -> Self {
+        self.quality = quality;
+        self
     }
 
     /// Receives a packet from the Client Socket
@@ -23,6 +34,13 @@ impl PacketReceiver {
     pub fn server_addr(&self) -> ServerAddr {
         self.inner.server_addr()
     }
+
+    /// Gets the round trip time, jitter & packet loss of the connection to
+    /// the Server. Returns None if the Socket was created without a
+    /// connection quality config, or before the first ping is answered
+    pub fn connection_quality(&self) -> Option<ConnectionQuality> {
+        self.quality.as_ref().and_then(|quality| quality.quality())
+    }
 }
 
 // Trait
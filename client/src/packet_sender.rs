@@ -0,0 +1,54 @@
+use super::server_addr::ServerAddr;
+
+// Impl
+
+/// Handles sending messages to the Server for a given Client Socket
+#[derive(Clone)]
+pub struct PacketSender {
+    inner: Box<dyn PacketSenderTrait>,
+}
+
+impl PacketSender {
+    /// Create a new PacketSender
+    pub fn new(inner: Box<dyn PacketSenderTrait>) -> Self {
+        PacketSender { inner }
+    }
+
+    /// Send a Packet to the Server
+    pub fn send(&self, payload: &[u8]) {
+        self.inner.send(payload);
+    }
+
+    /// Get the Server's Socket address
+    pub fn server_addr(&self) -> ServerAddr {
+        self.inner.server_addr()
+    }
+}
+
+// Trait
+
+/// Handles sending messages to the Server for a given Client Socket
+pub trait PacketSenderTrait: PacketSenderClone + Send + Sync {
+    /// Send a Packet to the Server
+    fn send(&self, payload: &[u8]);
+    /// Get the Server's Socket address
+    fn server_addr(&self) -> ServerAddr;
+}
+
+/// Used to clone Box<dyn PacketSenderTrait>
+pub trait PacketSenderClone {
+    /// Clone the boxed PacketSender
+    fn clone_box(&self) -> Box<dyn PacketSenderTrait>;
+}
+
+impl<T: 'static + PacketSenderTrait + Clone> PacketSenderClone for T {
+    fn clone_box(&self) -> Box<dyn PacketSenderTrait> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn PacketSenderTrait> {
+    fn clone(&self) -> Box<dyn PacketSenderTrait> {
+        PacketSenderClone::clone_box(self.as_ref())
+    }
+}
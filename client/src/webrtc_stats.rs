@@ -0,0 +1,227 @@
+use std::{collections::HashMap, time::Duration};
+
+/// A snapshot of the browser's statistics for the WebRTC connection to the
+/// Server, read from `RTCPeerConnection.getStats()`
+#[derive(Clone, Debug, Default)]
+pub struct WebRtcStats {
+    /// The ICE candidate pair carrying the connection, once one is selected
+    pub selected_candidate_pair: Option<CandidatePairStats>,
+    /// The current round trip time over the selected candidate pair
+    pub current_rtt: Option<Duration>,
+    /// Bytes sent over the selected candidate pair
+    pub bytes_sent: u64,
+    /// Bytes received over the selected candidate pair
+    pub bytes_received: u64,
+    /// Packets sent over the selected candidate pair
+    pub packets_sent: u64,
+    /// Packets received over the selected candidate pair
+    pub packets_received: u64,
+    /// Bytes queued on the data channel which haven't been sent yet
+    pub buffered_amount: u32,
+}
+
+/// The statistics of an ICE candidate pair
+#[derive(Clone, Debug, Default)]
+pub struct CandidatePairStats {
+    /// The state of the pair's connectivity checks, e.g. "succeeded"
+    pub state: Option<String>,
+    /// The candidate on this side of the connection
+    pub local: CandidateStats,
+    /// The candidate on the Server's side of the connection
+    pub remote: CandidateStats,
+}
+
+/// The statistics of an ICE candidate
+#[derive(Clone, Debug, Default)]
+pub struct CandidateStats {
+    /// The candidate's IP address, if the browser exposes it
+    pub address: Option<String>,
+    /// The candidate's port
+    pub port: Option<u16>,
+    /// The candidate's protocol, "udp" or "tcp"
+    pub protocol: Option<String>,
+    /// The candidate's type, "host", "srflx", "prflx" or "relay"
+    pub candidate_type: Option<String>,
+}
+
+/// A stats object from an RTCStatsReport, whose fields can be read by name
+pub(crate) trait StatsObject {
+    /// Reads a string field
+    fn string(&self, name: &str) -> Option<String>;
+    /// Reads a number field
+    fn number(&self, name: &str) -> Option<f64>;
+    /// Reads a boolean field, which is false if missing
+    fn boolean(&self, name: &str) -> bool;
+
+    /// Reads a counter field, which is 0 if missing
+    fn count(&self, name: &str) -> u64 {
+        self.number(name).map(|value| value as u64).unwrap_or(0)
+    }
+}
+
+impl WebRtcStats {
+    /// Reads the stats from the objects of an RTCStatsReport, keyed by their
+    /// ids, given the data channel's bufferedAmount, which the report doesn't
+    /// include
+    pub(crate) fn from_entries<S: StatsObject>(
+        entries: &HashMap<String, S>,
+        buffered_amount: u32,
+    ) -> Self {
+        let mut stats = WebRtcStats {
+            buffered_amount,
+            ..Default::default()
+        };
+        if let Some(pair) = selected_pair(entries) {
+            stats.current_rtt = pair
+                .number("currentRoundTripTime")
+                .map(Duration::from_secs_f64);
+            stats.bytes_sent = pair.count("bytesSent");
+            stats.bytes_received = pair.count("bytesReceived");
+            stats.packets_sent = pair.count("packetsSent");
+            stats.packets_received = pair.count("packetsReceived");
+            stats.selected_candidate_pair = Some(CandidatePairStats {
+                state: pair.string("state"),
+                local: candidate(entries, pair.string("localCandidateId")),
+                remote: candidate(entries, pair.string("remoteCandidateId")),
+            });
+        }
+        stats
+    }
+}
+
+// The transport names the selected pair in most browsers. Otherwise it is the
+// pair marked `selected`, as in Firefox, or the nominated pair which succeeded
+fn selected_pair<S: StatsObject>(entries: &HashMap<String, S>) -> Option<&S> {
+    let of_type = |kind: &'static str| {
+        entries
+            .values()
+            .filter(move |stats| stats.string("type").as_deref() == Some(kind))
+    };
+    of_type("transport")
+        .find_map(|transport| transport.string("selectedCandidatePairId"))
+        .and_then(|id| entries.get(&id))
+        .or_else(|| {
+            of_type("candidate-pair").find(|pair| {
+                pair.boolean("selected")
+                    || (pair.boolean("nominated")
+                        && pair.string("state").as_deref() == Some("succeeded"))
+            })
+        })
+}
+
+fn candidate<S: StatsObject>(entries: &HashMap<String, S>, id: Option<String>) -> CandidateStats {
+    match id.and_then(|id| entries.get(&id)) {
+        Some(candidate) => CandidateStats {
+            address: candidate
+                .string("address")
+                .or_else(|| candidate.string("ip")),
+            port: candidate.number("port").map(|port| port as u16),
+            protocol: candidate.string("protocol"),
+            candidate_type: candidate.string("candidateType"),
+        },
+        None => CandidateStats::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use super::{StatsObject, WebRtcStats};
+
+    enum Field {
+        Text(&'static str),
+        Number(f64),
+        Flag(bool),
+    }
+
+    struct FakeStats(Vec<(&'static str, Field)>);
+
+    impl FakeStats {
+        fn field(&self, name: &str) -> Option<&Field> {
+            self.0
+                .iter()
+                .find(|(field_name, _)| *field_name == name)
+                .map(|(_, field)| field)
+        }
+    }
+
+    impl StatsObject for FakeStats {
+        fn string(&self, name: &str) -> Option<String> {
+            match self.field(name)? {
+                Field::Text(text) => Some(text.to_string()),
+                _ => None,
+            }
+        }
+
+        fn number(&self, name: &str) -> Option<f64> {
+            match self.field(name)? {
+                Field::Number(number) => Some(*number),
+                _ => None,
+            }
+        }
+
+        fn boolean(&self, name: &str) -> bool {
+            matches!(self.field(name), Some(Field::Flag(true)))
+        }
+    }
+
+    fn candidate(address: &'static str, candidate_type: &'static str) -> FakeStats {
+        FakeStats(vec![
+            ("type", Field::Text("local-candidate")),
+            ("address", Field::Text(address)),
+            ("port", Field::Number(14192.0)),
+            ("protocol", Field::Text("udp")),
+            ("candidateType", Field::Text(candidate_type)),
+        ])
+    }
+
+    #[test]
+    fn reads_the_selected_candidate_pair() {
+        let pair = |state: &'static str, rtt: f64, selected: bool| {
+            FakeStats(vec![
+                ("type", Field::Text("candidate-pair")),
+                ("state", Field::Text(state)),
+                ("selected", Field::Flag(selected)),
+                ("currentRoundTripTime", Field::Number(rtt)),
+                ("bytesSent", Field::Number(1200.0)),
+                ("packetsReceived", Field::Number(7.0)),
+                ("localCandidateId", Field::Text("local")),
+                ("remoteCandidateId", Field::Text("remote")),
+            ])
+        };
+        let mut entries = HashMap::new();
+        entries.insert("local".to_string(), candidate("10.0.0.2", "host"));
+        entries.insert("remote".to_string(), candidate("10.0.0.1", "srflx"));
+        entries.insert("failed".to_string(), pair("failed", 1.0, false));
+        entries.insert("selected".to_string(), pair("succeeded", 0.025, true));
+
+        // Without a transport naming the pair, the pair marked selected is used
+        let stats = WebRtcStats::from_entries(&entries, 64);
+        assert_eq!(stats.current_rtt, Some(Duration::from_millis(25)));
+        assert_eq!((stats.bytes_sent, stats.bytes_received), (1200, 0));
+        assert_eq!(stats.packets_received, 7);
+        assert_eq!(stats.buffered_amount, 64);
+        let selected = stats.selected_candidate_pair.unwrap();
+        assert_eq!(selected.state.as_deref(), Some("succeeded"));
+        assert_eq!(selected.remote.address.as_deref(), Some("10.0.0.1"));
+        assert_eq!(selected.remote.candidate_type.as_deref(), Some("srflx"));
+        assert_eq!(selected.local.port, Some(14192));
+        assert_eq!(selected.local.protocol.as_deref(), Some("udp"));
+
+        // The transport's choice takes precedence
+        entries.insert(
+            "transport".to_string(),
+            FakeStats(vec![
+                ("type", Field::Text("transport")),
+                ("selectedCandidatePairId", Field::Text("failed")),
+            ]),
+        );
+        let stats = WebRtcStats::from_entries(&entries, 0);
+        assert_eq!(stats.current_rtt, Some(Duration::from_secs(1)));
+
+        assert!(WebRtcStats::from_entries::<FakeStats>(&HashMap::new(), 0)
+            .selected_candidate_pair
+            .is_none());
+    }
+}
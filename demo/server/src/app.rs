@@ -25,7 +25,7 @@ impl App {
         let shared_config = shared_config();
 
         let mut socket = Socket::new(&shared_config);
-        socket.listen(&server_address);
+        socket.listen(&server_address).expect("could not listen");
 
         App {
             packet_sender: socket.packet_sender(),
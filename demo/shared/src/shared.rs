@@ -10,7 +10,11 @@ pub fn shared_config() -> SocketConfig {
     //        incoming_latency: 500,
     //        incoming_jitter: 1,
     //        incoming_loss: 0.0,
-    //        incoming_corruption: 0.0
+    //        incoming_corruption: 0.0,
+    //        outgoing_latency: 100,
+    //        outgoing_jitter: 10,
+    //        outgoing_loss: 0.05,
+    //        ..Default::default()
     //    });
 
     return SocketConfig::new(link_condition, None);
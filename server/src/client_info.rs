@@ -0,0 +1,62 @@
+use std::net::SocketAddr;
+
+use naia_socket_shared::{ConnectionQuality, Instant};
+
+/// What a Client is connected over
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Transport {
+    /// A UDP socket
+    Udp,
+    /// A WebRTC data channel
+    WebRtc,
+    /// A LoopbackHub in the same process
+    Loopback,
+}
+
+/// Information about a connected Client
+#[derive(Clone, Debug)]
+pub struct ClientInfo {
+    /// The address the Client is connected from
+    pub address: SocketAddr,
+    /// What the Client is connected over
+    pub transport: Transport,
+    /// When the Client connected
+    pub connected_since: Instant,
+    /// When the last packet from the Client arrived, or when it connected if
+    /// none have
+    pub last_received: Instant,
+    /// Bytes received from the Client, including control packets
+    pub bytes_in: u64,
+    /// Packets received from the Client, including control packets
+    pub packets_in: u64,
+    /// Bytes sent to the Client, including control packets
+    pub bytes_out: u64,
+    /// Packets sent to the Client, including control packets
+    pub packets_out: u64,
+    /// The identity the application has authenticated the Client as, if any.
+    /// Set with `Socket.set_identity()`
+    pub identity: Option<String>,
+    /// The round trip time, jitter & packet loss of the connection, as last
+    /// reported by the Client's pings. None if the Socket was created without
+    /// a connection quality config, or before the Client has measured anything
+    pub quality: Option<ConnectionQuality>,
+    pub(crate) pings_received: u32,
+}
+
+impl ClientInfo {
+    pub(crate) fn new(address: SocketAddr, transport: Transport, now: Instant) -> Self {
+        ClientInfo {
+            address,
+            transport,
+            connected_since: now.clone(),
+            last_received: now,
+            bytes_in: 0,
+            packets_in: 0,
+            bytes_out: 0,
+            packets_out: 0,
+            identity: None,
+            quality: None,
+            pings_received: 0,
+        }
+    }
+}
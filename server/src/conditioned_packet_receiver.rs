@@ -2,16 +2,19 @@ use std::net::SocketAddr;
 
 use crossbeam::channel::Receiver;
 
-use naia_socket_shared::{link_condition_logic, LinkConditionerConfig, TimeQueue};
+use naia_socket_shared::LinkDirection;
 
-use super::{error::NaiaServerSocketError, packet_receiver::PacketReceiverTrait};
+use super::{
+    error::NaiaServerSocketError,
+    link_conditioner_profiles::{ClientConditioners, LinkConditionerProfiles},
+    packet_receiver::PacketReceiverTrait,
+};
 
 /// Used to receive packets from the Server Socket
 #[derive(Clone)]
 pub struct ConditionedPacketReceiverImpl {
     channel_receiver: Receiver<Result<(SocketAddr, Box<[u8]>), NaiaServerSocketError>>,
-    link_conditioner_config: LinkConditionerConfig,
-    time_queue: TimeQueue<(SocketAddr, Box<[u8]>)>,
+    link_conditioners: ClientConditioners,
     last_payload: Option<Box<[u8]>>,
 }
 
@@ -19,12 +22,14 @@ impl ConditionedPacketReceiverImpl {
     /// Creates a new PacketReceiver
     pub fn new(
         channel_receiver: Receiver<Result<(SocketAddr, Box<[u8]>), NaiaServerSocketError>>,
-        link_conditioner_config: &LinkConditionerConfig,
+        link_conditioner_profiles: &LinkConditionerProfiles,
     ) -> Self {
         ConditionedPacketReceiverImpl {
             channel_receiver,
-            link_conditioner_config: link_conditioner_config.clone(),
-            time_queue: TimeQueue::new(),
+            link_conditioners: ClientConditioners::new(
+                link_conditioner_profiles.clone(),
+                LinkDirection::Incoming,
+            ),
             last_payload: None,
         }
     }
@@ -39,11 +44,7 @@ impl PacketReceiverTrait for ConditionedPacketReceiverImpl {
                         break; //TODO: Handle error here
                     }
                     Ok(packet) => {
-                        link_condition_logic::process_packet(
-                            &self.link_conditioner_config,
-                            &mut self.time_queue,
-                            packet,
-                        );
+                        self.link_conditioners.process_packet(packet);
                     }
                 },
                 Err(_) => {
@@ -52,8 +53,7 @@ impl PacketReceiverTrait for ConditionedPacketReceiverImpl {
             }
         }
 
-        if self.time_queue.has_item() {
-            let (address, payload) = self.time_queue.pop_item().unwrap();
+        if let Some((address, payload)) = self.link_conditioners.pop_item() {
             self.last_payload = Some(payload);
             return Ok(Some((address, self.last_payload.as_ref().unwrap())));
         } else {
@@ -0,0 +1,301 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crossbeam::channel::Sender;
+
+use naia_socket_shared::{ConnectionQuality, DisconnectReason, RealClock, SharedClock, Timer};
+
+use super::{
+    client_info::{ClientInfo, Transport},
+    server_event::ServerEvent,
+};
+
+/// How long a UDP Client can go unheard from before it is disconnected, when
+/// the Socket was created without a keepalive config to set the idle timeout
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Keeps track of which Clients are connected, raising an event whenever one
+/// connects or disconnects, unless the event queue is full. A Client connects
+/// when its WebRTC session is established or its first packet arrives. Clones
+/// share the same Clients
+#[derive(Clone)]
+pub struct Connections {
+    transport: Transport,
+    state: Arc<Mutex<ConnectionsState>>,
+    event_sender: Sender<ServerEvent>,
+    clock: SharedClock,
+}
+
+struct ConnectionsState {
+    clients: HashMap<SocketAddr, ClientInfo>,
+    kicked: HashSet<SocketAddr>,
+    idle_sweep: Option<IdleSweep>,
+}
+
+// Disconnects idle Clients as the Connections is swept, once per idle timeout
+struct IdleSweep {
+    idle_timeout: Duration,
+    timer: Timer,
+}
+
+impl Connections {
+    /// Creates a new Connections, for Clients connected over the given
+    /// Transport, which raises events through the given sender
+    pub fn new(transport: Transport, event_sender: Sender<ServerEvent>) -> Self {
+        Connections {
+            transport,
+            state: Arc::new(Mutex::new(ConnectionsState {
+                clients: HashMap::new(),
+                kicked: HashSet::new(),
+                idle_sweep: None,
+            })),
+            event_sender,
+            clock: Arc::new(RealClock),
+        }
+    }
+
+    /// Reads the time from the given Clock, rather than the wall clock, when
+    /// recording packets & timing Clients out. Must be called before
+    /// `with_idle_timeout()`
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Gets the Clock the Connections reads the time from
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
+    /// Disconnects Clients which haven't been heard from within the given
+    /// timeout, checking as packets arrive. Used when nothing else will, so
+    /// Clients which stop sending are eventually forgotten
+    pub fn with_idle_timeout(self, idle_timeout: Duration) -> Self {
+        self.state.lock().unwrap().idle_sweep = Some(IdleSweep {
+            idle_timeout,
+            timer: Timer::with_clock(idle_timeout, self.clock.clone()),
+        });
+        self
+    }
+
+    /// Connects the Client at the given address, if it isn't already
+    /// connected & hasn't been kicked. Only WebRTC Clients connect before
+    /// their first packet
+    #[cfg(feature = "use-webrtc")]
+    pub fn connect(&self, address: &SocketAddr) {
+        self.state.lock().unwrap().client(address, self);
+    }
+
+    /// Records that a packet of the given size arrived from the Client at the
+    /// given address, which connects it if it wasn't already
+    pub fn received(&self, address: &SocketAddr, bytes: usize) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(client) = state.client(address, self) {
+            client.last_received = self.clock.now();
+            client.bytes_in += bytes as u64;
+            client.packets_in += 1;
+        }
+    }
+
+    /// Records that a packet of the given size was sent to the Client at the
+    /// given address
+    pub fn sent(&self, address: &SocketAddr, bytes: usize) {
+        if let Some(client) = self.state.lock().unwrap().clients.get_mut(address) {
+            client.bytes_out += bytes as u64;
+            client.packets_out += 1;
+        }
+    }
+
+    /// Records a ping from the Client at the given address, along with the
+    /// connection quality it reported, if any. Returns the number of pings it
+    /// has sent, or None if it isn't connected
+    pub fn pinged(&self, address: &SocketAddr, report: Option<ConnectionQuality>) -> Option<u32> {
+        let mut state = self.state.lock().unwrap();
+        let client = state.clients.get_mut(address)?;
+        client.pings_received = client.pings_received.wrapping_add(1);
+        if let Some(report) = report {
+            client.quality = Some(report.as_seen_by_peer());
+        }
+        Some(client.pings_received)
+    }
+
+    /// Disconnects the Client at the given address for the given reason.
+    /// Returns whether it was connected
+    pub fn disconnect(&self, address: &SocketAddr, reason: DisconnectReason) -> bool {
+        let was_connected = self.state.lock().unwrap().clients.remove(address).is_some();
+        if was_connected {
+            let _ = self
+                .event_sender
+                .try_send(ServerEvent::Disconnected(*address, reason));
+        }
+        was_connected
+    }
+
+    /// Disconnects the Clients which haven't been heard from within the idle
+    /// timeout the Connections was created with, if it has one & it has passed
+    /// since the last sweep
+    pub fn sweep_idle(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(idle_sweep) = &mut state.idle_sweep {
+            if idle_sweep.timer.ringing() {
+                idle_sweep.timer.reset();
+                let idle_timeout = idle_sweep.idle_timeout;
+                state.disconnect_idle(idle_timeout, self);
+            }
+        }
+    }
+
+    /// Disconnects every Client which hasn't been heard from within the given
+    /// timeout
+    pub fn disconnect_idle(&self, idle_timeout: Duration) {
+        self.state
+            .lock()
+            .unwrap()
+            .disconnect_idle(idle_timeout, self);
+    }
+
+    /// Disconnects the Client at the given address, & refuses it until it is
+    /// unkicked
+    pub fn kick(&self, address: &SocketAddr) {
+        self.state.lock().unwrap().kicked.insert(*address);
+        self.disconnect(address, DisconnectReason::Kicked);
+    }
+
+    /// Stops refusing the given address. Returns whether it had been kicked
+    pub fn unkick(&self, address: &SocketAddr) -> bool {
+        self.state.lock().unwrap().kicked.remove(address)
+    }
+
+    /// Returns whether the given address has been kicked
+    pub fn is_kicked(&self, address: &SocketAddr) -> bool {
+        self.state.lock().unwrap().kicked.contains(address)
+    }
+
+    /// Sets the identity of the Client at the given address. Returns whether
+    /// it is connected
+    pub fn set_identity(&self, address: &SocketAddr, identity: String) -> bool {
+        match self.state.lock().unwrap().clients.get_mut(address) {
+            Some(client) => {
+                client.identity = Some(identity);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Gets the information of the Client at the given address, if it is
+    /// connected
+    pub fn get(&self, address: &SocketAddr) -> Option<ClientInfo> {
+        self.state.lock().unwrap().clients.get(address).cloned()
+    }
+
+    /// Gets the information of every connected Client
+    pub fn all(&self) -> Vec<ClientInfo> {
+        self.state
+            .lock()
+            .unwrap()
+            .clients
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the addresses of every connected Client
+    pub fn addresses(&self) -> Vec<SocketAddr> {
+        self.state.lock().unwrap().clients.keys().copied().collect()
+    }
+
+    /// Forgets every Client, without raising events
+    pub fn clear(&self) {
+        self.state.lock().unwrap().clients.clear();
+    }
+}
+
+impl ConnectionsState {
+    fn disconnect_idle(&mut self, idle_timeout: Duration, connections: &Connections) {
+        let now = connections.clock.now();
+        self.clients.retain(|address, client| {
+            if now.duration_since(&client.last_received) <= idle_timeout {
+                return true;
+            }
+            let _ = connections.event_sender.try_send(ServerEvent::Disconnected(
+                *address,
+                DisconnectReason::TimedOut,
+            ));
+            false
+        });
+    }
+
+    // Gets the Client at the given address, connecting it if it isn't
+    // already. Returns None if it has been kicked
+    fn client(
+        &mut self,
+        address: &SocketAddr,
+        connections: &Connections,
+    ) -> Option<&mut ClientInfo> {
+        if self.kicked.contains(address) {
+            return None;
+        }
+        Some(self.clients.entry(*address).or_insert_with(|| {
+            let _ = connections
+                .event_sender
+                .try_send(ServerEvent::Connected(*address));
+            ClientInfo::new(*address, connections.transport, connections.clock.now())
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use crossbeam::channel;
+
+    use naia_socket_shared::{DisconnectReason, ManualClock};
+
+    use super::Connections;
+    use crate::{client_info::Transport, server_event::ServerEvent};
+
+    #[test]
+    fn forgets_idle_clients_when_swept() {
+        let (event_sender, event_receiver) = channel::unbounded();
+        let clock = Arc::new(ManualClock::new());
+        let connections = Connections::new(Transport::Udp, event_sender)
+            .with_clock(clock.clone())
+            .with_idle_timeout(Duration::from_millis(50));
+        let idle_address = "127.0.0.1:5000".parse().unwrap();
+        let active_address = "127.0.0.1:5001".parse().unwrap();
+
+        connections.received(&idle_address, 10);
+        clock.advance(Duration::from_millis(100));
+        connections.received(&active_address, 10);
+        connections.sweep_idle();
+
+        assert_eq!(connections.addresses(), vec![active_address]);
+        assert_eq!(
+            event_receiver.try_iter().collect::<Vec<_>>(),
+            vec![
+                ServerEvent::Connected(idle_address),
+                ServerEvent::Connected(active_address),
+                ServerEvent::Disconnected(idle_address, DisconnectReason::TimedOut),
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_events_when_the_queue_is_full() {
+        let (event_sender, event_receiver) = channel::bounded(2);
+        let connections = Connections::new(Transport::Udp, event_sender);
+
+        for port in 5000..5010 {
+            connections.received(&format!("127.0.0.1:{}", port).parse().unwrap(), 10);
+        }
+
+        assert_eq!(connections.addresses().len(), 10);
+        assert_eq!(event_receiver.try_iter().count(), 2);
+    }
+}
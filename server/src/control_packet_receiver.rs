@@ -0,0 +1,94 @@
+use std::net::SocketAddr;
+
+use naia_socket_shared::{ControlPacket, Timestamp};
+
+use super::{
+    connections::Connections, error::NaiaServerSocketError, keepalive::Keepalive,
+    packet_receiver::PacketReceiverTrait, packet_sender::PacketSender,
+};
+
+/// Used to receive packets from the Server Socket, while answering the
+/// control packets of Clients which sync clocks & measure the connection's
+/// quality, & saying goodbye. Control packets are consumed rather than
+/// returned
+#[derive(Clone)]
+pub struct ControlPacketReceiverImpl {
+    inner_receiver: Box<dyn PacketReceiverTrait>,
+    packet_sender: PacketSender,
+    connections: Connections,
+    time_sync: bool,
+    quality: bool,
+    keepalive: Option<Keepalive>,
+    last_packet: Option<(SocketAddr, Box<[u8]>)>,
+}
+
+impl ControlPacketReceiverImpl {
+    /// Creates a new ControlPacketReceiverImpl, which answers through the
+    /// given PacketSender. Time probes are answered if `time_sync` is true,
+    /// pings from connected Clients are answered if `quality` is true, &
+    /// goodbyes are handled if a Keepalive is given
+    pub fn new(
+        inner_receiver: Box<dyn PacketReceiverTrait>,
+        packet_sender: PacketSender,
+        connections: Connections,
+        time_sync: bool,
+        quality: bool,
+        keepalive: Option<Keepalive>,
+    ) -> Self {
+        ControlPacketReceiverImpl {
+            inner_receiver,
+            packet_sender,
+            connections,
+            time_sync,
+            quality,
+            keepalive,
+            last_packet: None,
+        }
+    }
+
+    fn handle(&self, address: &SocketAddr, packet: ControlPacket) {
+        let reply = match packet {
+            ControlPacket::TimeProbe { id } if self.time_sync => {
+                let now = Timestamp::now();
+                ControlPacket::TimeReply {
+                    id,
+                    received: now,
+                    sent: now,
+                }
+            }
+            ControlPacket::Ping { id, report } if self.quality => {
+                let pings_received = match self.connections.pinged(address, report) {
+                    Some(pings_received) => pings_received,
+                    None => return,
+                };
+                ControlPacket::Pong { id, pings_received }
+            }
+            ControlPacket::Goodbye => {
+                if let Some(keepalive) = &self.keepalive {
+                    keepalive.goodbye(address);
+                }
+                return;
+            }
+            _ => return,
+        };
+        self.packet_sender.send(address, &reply.to_bytes());
+    }
+}
+
+impl PacketReceiverTrait for ControlPacketReceiverImpl {
+    fn receive(&mut self) -> Result<Option<(SocketAddr, &[u8])>, NaiaServerSocketError> {
+        loop {
+            match self.inner_receiver.receive()? {
+                None => return Ok(None),
+                Some((address, payload)) => match ControlPacket::from_bytes(payload) {
+                    Some(packet) => self.handle(&address, packet),
+                    None => {
+                        self.last_packet = Some((address, payload.into()));
+                        let (address, payload) = self.last_packet.as_ref().unwrap();
+                        return Ok(Some((*address, payload)));
+                    }
+                },
+            }
+        }
+    }
+}
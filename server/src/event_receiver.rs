@@ -0,0 +1,21 @@
+use crossbeam::channel::Receiver;
+
+use super::server_event::ServerEvent;
+
+/// Used to receive events from the Server Socket
+#[derive(Clone)]
+pub struct EventReceiver {
+    channel_receiver: Receiver<ServerEvent>,
+}
+
+impl EventReceiver {
+    /// Creates a new EventReceiver
+    pub fn new(channel_receiver: Receiver<ServerEvent>) -> Self {
+        EventReceiver { channel_receiver }
+    }
+
+    /// Receives an event from the Server Socket, if any are waiting
+    pub fn receive(&mut self) -> Option<ServerEvent> {
+        self.channel_receiver.try_recv().ok()
+    }
+}
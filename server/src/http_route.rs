@@ -0,0 +1,257 @@
+use std::{
+    fs,
+    net::SocketAddr,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+/// An HTTP request received by the signaling server
+#[derive(Clone, Debug)]
+pub struct HttpRequest {
+    /// The request method, e.g. "GET"
+    pub method: String,
+    /// The request path, without the query string
+    pub path: String,
+    /// The query string, if any
+    pub query: Option<String>,
+    /// The request headers, with lowercase names
+    pub headers: Vec<(String, String)>,
+    /// The request body
+    pub body: Vec<u8>,
+    /// The address of the client which sent the request
+    pub remote_addr: SocketAddr,
+}
+
+impl HttpRequest {
+    /// Gets the value of the first header with the given name, ignoring case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.headers
+            .iter()
+            .find(|(header_name, _)| *header_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// An HTTP response to be sent by the signaling server
+#[derive(Clone, Debug)]
+pub struct HttpResponse {
+    /// The response status code
+    pub status: u16,
+    /// Additional response headers. Content-Length is always added when the
+    /// response is written
+    pub headers: Vec<(String, String)>,
+    /// The response body
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Creates a new, empty HttpResponse with the given status code
+    pub fn new(status: u16) -> Self {
+        HttpResponse {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Creates a 200 OK HttpResponse with the given content type and body
+    pub fn ok(content_type: &str, body: Vec<u8>) -> Self {
+        HttpResponse::new(200)
+            .with_header("Content-Type", content_type)
+            .with_body(body)
+    }
+
+    /// Creates a 200 OK HttpResponse with a plain text body
+    pub fn text(body: &str) -> Self {
+        HttpResponse::ok("text/plain; charset=utf-8", body.as_bytes().to_vec())
+    }
+
+    /// Creates a 200 OK HttpResponse with a JSON body
+    pub fn json(body: &str) -> Self {
+        HttpResponse::ok("application/json", body.as_bytes().to_vec())
+    }
+
+    /// Creates an empty 404 Not Found HttpResponse
+    pub fn not_found() -> Self {
+        HttpResponse::new(404)
+    }
+
+    /// Adds a header to the HttpResponse
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Replaces the body of the HttpResponse
+    pub fn with_body(mut self, body: Vec<u8>) -> Self {
+        self.body = body;
+        self
+    }
+}
+
+/// A function which handles requests to an HttpRoute
+pub type RouteHandler = Arc<dyn Fn(&HttpRequest) -> HttpResponse + Send + Sync>;
+
+/// An application-defined route to be served by the signaling server,
+/// alongside the WebRTC session endpoint
+#[derive(Clone)]
+#[cfg_attr(feature = "use-udp", allow(dead_code))]
+pub struct HttpRoute {
+    method: String,
+    path: String,
+    prefix: bool,
+    handler: RouteHandler,
+}
+
+impl HttpRoute {
+    /// Creates a new HttpRoute which handles requests with the given method
+    /// to exactly the given path
+    pub fn new(
+        method: &str,
+        path: &str,
+        handler: impl Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    ) -> Self {
+        HttpRoute {
+            method: method.to_uppercase(),
+            path: normalize_path(path),
+            prefix: false,
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Creates a new HttpRoute which handles GET requests to the given path
+    pub fn get(
+        path: &str,
+        handler: impl Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    ) -> Self {
+        HttpRoute::new("GET", path, handler)
+    }
+
+    /// Creates a new HttpRoute which handles POST requests to the given path
+    pub fn post(
+        path: &str,
+        handler: impl Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    ) -> Self {
+        HttpRoute::new("POST", path, handler)
+    }
+
+    /// Creates a liveness route which responds to GET requests with "OK"
+    pub fn health(path: &str) -> Self {
+        HttpRoute::get(path, |_| HttpResponse::text("OK"))
+    }
+
+    /// Creates a route which responds to GET requests with the JSON produced
+    /// by the given function, e.g. the current player count & version
+    pub fn json(path: &str, json_fn: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        HttpRoute::get(path, move |_| HttpResponse::json(&json_fn()))
+    }
+
+    /// Creates a route which serves files from the given directory for GET
+    /// requests to any path beneath `url_prefix`. Requests for a directory
+    /// are served its `index.html`
+    pub fn static_files(url_prefix: &str, dir: impl Into<PathBuf>) -> Self {
+        let dir: PathBuf = dir.into();
+        let url_prefix = normalize_path(url_prefix);
+        let strip_prefix = url_prefix.clone();
+        let mut route = HttpRoute::get(&url_prefix, move |request| {
+            let relative_path = request
+                .path
+                .strip_prefix(strip_prefix.as_str())
+                .unwrap_or("");
+            serve_file(&dir, relative_path)
+        });
+        route.prefix = true;
+        route
+    }
+
+    /// Returns whether this route handles requests with the given method &
+    /// path
+    #[cfg_attr(feature = "use-udp", allow(dead_code))]
+    pub(crate) fn matches(&self, method: &str, path: &str) -> bool {
+        if self.method != method {
+            return false;
+        }
+        if !self.prefix {
+            return self.path == path;
+        }
+        if self.path == "/" || self.path == path {
+            return true;
+        }
+        path.starts_with(&self.path) && path[self.path.len()..].starts_with('/')
+    }
+
+    /// Handles a request to this route
+    #[cfg_attr(feature = "use-udp", allow(dead_code))]
+    pub(crate) fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        (self.handler)(request)
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    format!("/{}", path.trim_matches('/'))
+}
+
+fn serve_file(dir: &Path, relative_path: &str) -> HttpResponse {
+    let mut file_path = dir.to_path_buf();
+    for component in Path::new(relative_path.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => file_path.push(part),
+            Component::CurDir => {}
+            // Never serve anything outside of the given directory
+            _ => return HttpResponse::not_found(),
+        }
+    }
+    if file_path.is_dir() {
+        file_path.push("index.html");
+    }
+
+    match fs::read(&file_path) {
+        Ok(contents) => HttpResponse::ok(content_type(&file_path), contents),
+        Err(_) => HttpResponse::not_found(),
+    }
+}
+
+fn content_type(file_path: &Path) -> &'static str {
+    let extension = file_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("");
+    match extension {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" => "application/javascript",
+        "wasm" => "application/wasm",
+        "css" => "text/css",
+        "json" => "application/json",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "txt" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HttpResponse, HttpRoute};
+
+    #[test]
+    fn exact_route_matching() {
+        let route = HttpRoute::get("health", |_| HttpResponse::text("OK"));
+        assert!(route.matches("GET", "/health"));
+        assert!(!route.matches("POST", "/health"));
+        assert!(!route.matches("GET", "/health/more"));
+    }
+
+    #[test]
+    fn prefix_route_matching() {
+        let route = HttpRoute::static_files("/client", ".");
+        assert!(route.matches("GET", "/client"));
+        assert!(route.matches("GET", "/client/app.wasm"));
+        assert!(!route.matches("GET", "/clientele"));
+
+        let root = HttpRoute::static_files("/", ".");
+        assert!(root.matches("GET", "/index.html"));
+    }
+}
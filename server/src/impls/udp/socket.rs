@@ -3,7 +3,7 @@ use std::{
     net::{SocketAddr, UdpSocket},
 };
 
-use log::info;
+use log::{info, warn};
 
 use async_io::Async;
 use futures_channel::mpsc;
@@ -11,7 +11,10 @@ use futures_util::{pin_mut, select, FutureExt, StreamExt};
 
 use naia_socket_shared::SocketConfig;
 
-use crate::{error::NaiaServerSocketError, server_addrs::ServerAddrs};
+use crate::{
+    client_info::Transport, connections::Connections, error::NaiaServerSocketError,
+    http_route::HttpRoute, server_addrs::ServerAddrs,
+};
 
 const CLIENT_CHANNEL_SIZE: usize = 8;
 
@@ -25,9 +28,26 @@ pub struct Socket {
 }
 
 impl Socket {
-    /// Returns a new ServerSocket, listening at the given socket address
-    pub async fn listen(addrs: ServerAddrs, _config: SocketConfig) -> Self {
-        let socket = Async::new(UdpSocket::bind(&addrs.session_listen_addr).unwrap()).unwrap();
+    /// What Clients are connected over
+    pub const TRANSPORT: Transport = Transport::Udp;
+
+    /// Returns a new ServerSocket, listening at the given socket address, or
+    /// an error if it can't be bound
+    pub async fn listen(
+        addrs: ServerAddrs,
+        _config: SocketConfig,
+        http_routes: Vec<HttpRoute>,
+        // UDP has no sessions, so Clients connect with their first packet, &
+        // kicked Clients' packets are refused before they reach the Server
+        _connections: Connections,
+    ) -> Result<Self, NaiaServerSocketError> {
+        if !http_routes.is_empty() {
+            warn!("HTTP routes are only served by the WebRTC signaling server, ignoring them");
+        }
+
+        let socket = UdpSocket::bind(&addrs.session_listen_addr)
+            .and_then(Async::new)
+            .map_err(|err| NaiaServerSocketError::Wrapped(Box::new(err)))?;
 
         let (to_client_sender, to_client_receiver) = mpsc::channel(CLIENT_CHANNEL_SIZE);
 
@@ -36,13 +56,13 @@ impl Socket {
             addrs.session_listen_addr
         );
 
-        Socket {
+        Ok(Socket {
             socket,
             to_client_sender,
             to_client_receiver,
             receive_buffer: vec![0; 0x10000], /* Hopefully get rid of this one day.. next version
                                                * of webrtc-unreliable should make that happen */
-        }
+        })
     }
 
     pub async fn receive(&mut self) -> Result<(SocketAddr, Box<[u8]>), NaiaServerSocketError> {
@@ -1,168 +1,604 @@
 use std::{
-    net::{TcpListener, TcpStream},
+    collections::HashMap,
+    io::{Error as IoError, ErrorKind, Write},
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream},
     pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
     task::{Context, Poll},
+    time::Duration,
 };
 
 use async_dup::Arc;
 use futures_core::Stream;
-use http::{header, HeaderValue, Response};
-use log::info;
-use once_cell::sync::OnceCell;
+use http::{header, HeaderValue, Response, StatusCode};
+use log::{info, warn};
+use once_cell::sync::Lazy;
 use smol::{
+    future,
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
     prelude::*,
-    Async,
+    Async, Task, Timer,
 };
 use webrtc_unreliable::SessionEndpoint;
 
-use naia_socket_shared::SocketConfig;
+use naia_socket_shared::{Instant, IpFilter, SharedClock, SignalingConfig, SocketConfig};
 
-use crate::{executor, server_addrs::ServerAddrs};
+use crate::{
+    error::NaiaServerSocketError,
+    executor,
+    http_route::{HttpRequest, HttpResponse, HttpRoute},
+    server_addrs::ServerAddrs,
+};
+
+/// Everything served by a single signaling listener: the SessionEndpoint &
+/// IpFilter of each Socket, keyed by its RTC endpoint path, and any
+/// application routes, along with the path of the Socket which added them
+#[derive(Default)]
+struct SessionRoutes {
+    endpoints: HashMap<String, (SessionEndpoint, IpFilter)>,
+    http_routes: Vec<(String, HttpRoute)>,
+}
+
+impl SessionRoutes {
+    fn session_endpoint(&self, path: &str) -> Option<(SessionEndpoint, IpFilter)> {
+        self.endpoints.get(path.trim_start_matches('/')).cloned()
+    }
+
+    fn http_route(&self, method: &str, path: &str) -> Option<HttpRoute> {
+        self.http_routes
+            .iter()
+            .map(|(_, route)| route)
+            .find(|route| route.matches(method, path))
+            .cloned()
+    }
+
+    fn add(
+        &mut self,
+        rtc_path: &str,
+        endpoint: (SessionEndpoint, IpFilter),
+        http_routes: Vec<HttpRoute>,
+    ) {
+        self.endpoints.insert(rtc_path.to_string(), endpoint);
+        self.http_routes.extend(
+            http_routes
+                .into_iter()
+                .map(|route| (rtc_path.to_string(), route)),
+        );
+    }
+
+    fn remove(&mut self, rtc_path: &str) {
+        self.endpoints.remove(rtc_path);
+        self.http_routes.retain(|(owner, _)| owner != rtc_path);
+    }
+}
+
+type SharedSessionRoutes = std::sync::Arc<Mutex<SessionRoutes>>;
+
+// How often IP addresses whose session rate window has passed are forgotten
+const SESSION_PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+
+// The most IP addresses whose session requests are counted at once. Beyond
+// this, session requests from new addresses are refused until some windows
+// have passed
+const MAX_TRACKED_IPS: usize = 4096;
+
+/// Listener-wide state used to enforce the limits of a SignalingConfig
+struct SignalingLimiter {
+    config: SignalingConfig,
+    connections: AtomicUsize,
+    session_requests: Mutex<SessionRequests>,
+    clock: SharedClock,
+}
+
+/// The WebRTC session requests made from each IP address within its current
+/// rate window
+struct SessionRequests {
+    windows: HashMap<IpAddr, (Instant, u32)>,
+    last_prune: Instant,
+}
+
+impl SignalingLimiter {
+    fn new(config: SignalingConfig, clock: SharedClock) -> Self {
+        SignalingLimiter {
+            config,
+            connections: AtomicUsize::new(0),
+            session_requests: Mutex::new(SessionRequests {
+                windows: HashMap::new(),
+                last_prune: clock.now(),
+            }),
+            clock,
+        }
+    }
+
+    /// Reserves one of the listener's connection slots, if any are free
+    fn try_acquire_connection(
+        limiter: &std::sync::Arc<SignalingLimiter>,
+    ) -> Option<ConnectionPermit> {
+        let previous = limiter.connections.fetch_add(1, Ordering::SeqCst);
+        let permit = ConnectionPermit(limiter.clone());
+        if previous >= limiter.config.max_connections {
+            return None;
+        }
+        Some(permit)
+    }
+
+    /// Records a WebRTC session request from the given IP address, returning
+    /// whether it is within the rate limit
+    fn allow_session_request(&self, ip: IpAddr) -> bool {
+        let max_requests = match self.config.max_session_requests_per_ip {
+            Some(max_requests) => max_requests,
+            None => return true,
+        };
+        let window = self.config.session_rate_window;
+        let now = self.clock.now();
+        let mut session_requests = self.session_requests.lock().unwrap();
+
+        // Forget addresses whose window has passed
+        if now.duration_since(&session_requests.last_prune) >= SESSION_PRUNE_INTERVAL {
+            session_requests.last_prune = now.clone();
+            session_requests
+                .windows
+                .retain(|_, (window_start, _)| now.duration_since(window_start) < window);
+        }
+
+        let windows = &mut session_requests.windows;
+        if !windows.contains_key(&ip) && windows.len() >= MAX_TRACKED_IPS {
+            return false;
+        }
+        let (window_start, count) = windows.entry(ip).or_insert((now.clone(), 0));
+        if now.duration_since(window_start) >= window {
+            *window_start = now;
+            *count = 0;
+        }
+        *count += 1;
+        *count <= max_requests
+    }
+}
 
-static RTC_URL_PATH: OnceCell<String> = OnceCell::new();
+/// Holds one of a listener's connection slots until dropped
+struct ConnectionPermit(std::sync::Arc<SignalingLimiter>);
 
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.0.connections.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Reasons a request could not be read from a signaling connection
+enum RequestError {
+    Malformed,
+    Timeout,
+    HeaderTooLarge,
+    BodyTooLarge,
+}
+
+impl RequestError {
+    fn status(&self) -> StatusCode {
+        match self {
+            RequestError::Malformed => StatusCode::BAD_REQUEST,
+            RequestError::Timeout => StatusCode::REQUEST_TIMEOUT,
+            RequestError::HeaderTooLarge => StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            RequestError::BodyTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+}
+
+/// A signaling listener shared by the Sockets registered with it, which stops
+/// once the last of them is dropped
+struct SessionListener {
+    routes: SharedSessionRoutes,
+    signaling: SignalingConfig,
+    _task: Task<()>,
+}
+
+/// Signaling listeners running in this process, keyed by the address they are
+/// bound to, so that several Sockets can share a single listener
+static SESSION_LISTENERS: Lazy<Mutex<HashMap<SocketAddr, SessionListener>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A Socket's registration with a signaling listener. When dropped, the
+/// Socket's SessionEndpoint & HttpRoutes are removed, freeing its RTC endpoint
+/// path, & the listener stops if no other Socket is registered with it
+pub struct SessionRegistration {
+    // The address the listener is bound to
+    listen_addr: SocketAddr,
+    rtc_path: String,
+    routes: SharedSessionRoutes,
+    // Set when the listener is bound to port 0, & so isn't shared
+    own_listener: Option<Task<()>>,
+}
+
+impl Drop for SessionRegistration {
+    fn drop(&mut self) {
+        if self.own_listener.is_some() {
+            return;
+        }
+        let mut listeners = SESSION_LISTENERS.lock().unwrap();
+        let unused = {
+            let mut routes = self.routes.lock().unwrap();
+            routes.remove(&self.rtc_path);
+            routes.endpoints.is_empty()
+        };
+        if unused {
+            listeners.remove(&self.listen_addr);
+        }
+    }
+}
+
+/// Registers the Socket's SessionEndpoint at its RTC endpoint path, along with
+/// the Socket's HttpRoutes, starting a new signaling listener if one isn't
+/// already running at the session listen address. Returns an error if the
+/// listener can't be bound, or another Socket has registered the same path. A
+/// new listener's rate windows are timed by the given Clock
 pub fn start_session_server(
     server_addrs: ServerAddrs,
     config: SocketConfig,
     session_endpoint: SessionEndpoint,
-) {
-    RTC_URL_PATH
-        .set(format!("POST /{}", config.rtc_endpoint_path))
-        .unwrap();
-    executor::spawn(async move {
-        listen(server_addrs, config, session_endpoint.clone()).await;
+    http_routes: Vec<HttpRoute>,
+    clock: SharedClock,
+) -> Result<SessionRegistration, NaiaServerSocketError> {
+    let listen_addr = server_addrs.session_listen_addr;
+    let rtc_path = config.rtc_endpoint_path.trim_start_matches('/').to_string();
+    let endpoint = (session_endpoint, config.ip_filter);
+
+    let mut listeners = SESSION_LISTENERS.lock().unwrap();
+
+    // A listener bound to port 0 gets an ephemeral port, so it can't be shared
+    let shared = listen_addr.port() != 0;
+    if shared {
+        if let Some(listener) = listeners.get(&listen_addr) {
+            let mut routes = listener.routes.lock().unwrap();
+            if routes.endpoints.contains_key(&rtc_path) {
+                return Err(NaiaServerSocketError::Wrapped(Box::new(IoError::new(
+                    ErrorKind::AddrInUse,
+                    format!(
+                        "RTC endpoint path '/{}' is already in use on signaling listener {}",
+                        rtc_path, listen_addr
+                    ),
+                ))));
+            }
+            if config.signaling != listener.signaling {
+                warn!(
+                    "Signaling listener {} already has other limits, ignoring those of '/{}'",
+                    listen_addr, rtc_path
+                );
+            }
+            routes.add(&rtc_path, endpoint, http_routes);
+            info!(
+                "Session initiator available at POST http://{}/{}",
+                listen_addr, rtc_path
+            );
+            return Ok(SessionRegistration {
+                listen_addr,
+                rtc_path,
+                routes: listener.routes.clone(),
+                own_listener: None,
+            });
+        }
+    }
+
+    let tcp_listener = Async::<TcpListener>::bind(listen_addr)
+        .map_err(|err| NaiaServerSocketError::Wrapped(Box::new(err)))?;
+    let local_addr = tcp_listener
+        .get_ref()
+        .local_addr()
+        .map_err(|err| NaiaServerSocketError::Wrapped(Box::new(err)))?;
+
+    let mut routes = SessionRoutes::default();
+    routes.add(&rtc_path, endpoint, http_routes);
+    let routes: SharedSessionRoutes = std::sync::Arc::new(Mutex::new(routes));
+
+    let limiter = std::sync::Arc::new(SignalingLimiter::new(config.signaling.clone(), clock));
+    let routes_clone = routes.clone();
+    let task = executor::spawn(async move {
+        listen(tcp_listener, routes_clone, limiter).await;
+    });
+
+    let own_listener = if shared {
+        listeners.insert(
+            listen_addr,
+            SessionListener {
+                routes: routes.clone(),
+                signaling: config.signaling,
+                _task: task,
+            },
+        );
+        None
+    } else {
+        Some(task)
+    };
+
+    Ok(SessionRegistration {
+        listen_addr: local_addr,
+        rtc_path,
+        routes,
+        own_listener,
     })
-    .detach();
 }
 
 /// Listens for incoming connections and serves them.
 async fn listen(
-    server_addrs: ServerAddrs,
-    config: SocketConfig,
-    session_endpoint: SessionEndpoint,
+    listener: Async<TcpListener>,
+    routes: SharedSessionRoutes,
+    limiter: std::sync::Arc<SignalingLimiter>,
 ) {
-    let socket_address = server_addrs.session_listen_addr;
-
-    let listener = Async::<TcpListener>::bind(socket_address).unwrap();
-    info!(
-        "Session initiator available at POST http://{}/{}",
-        listener.get_ref().local_addr().unwrap(),
-        config.rtc_endpoint_path
-    );
+    let local_addr = listener.get_ref().local_addr().unwrap();
+    for rtc_path in routes.lock().unwrap().endpoints.keys() {
+        info!(
+            "Session initiator available at POST http://{}/{}",
+            local_addr, rtc_path
+        );
+    }
 
     loop {
         // Accept the next connection.
-        let (response_stream, _) = listener.accept().await.unwrap();
-
-        let session_endpoint_clone = session_endpoint.clone();
-
-        // Spawn a background task serving this connection.
-        executor::spawn(async move {
-            serve(session_endpoint_clone, Arc::new(response_stream)).await;
-        })
-        .detach();
+        let (response_stream, remote_addr) = listener.accept().await.unwrap();
+
+        match SignalingLimiter::try_acquire_connection(&limiter) {
+            Some(permit) => {
+                let routes_clone = routes.clone();
+                let stream = Arc::new(response_stream);
+
+                // Spawn a background task serving this connection.
+                executor::spawn(async move {
+                    serve(routes_clone, remote_addr, stream, permit).await;
+                })
+                .detach();
+            }
+            None => {
+                info!("Too many signaling connections, rejecting {}", remote_addr);
+                // Make a single attempt to write the response, without
+                // waiting, so a flood of connections can't be held open while
+                // they are rejected. The connection closes when dropped
+                let out = response_to_vec(empty_response(StatusCode::SERVICE_UNAVAILABLE));
+                let _ = response_stream.get_ref().write(&out);
+            }
+        }
     }
 }
 
 /// Reads a request from the client and sends it a response.
-async fn serve(mut session_endpoint: SessionEndpoint, mut stream: Arc<Async<TcpStream>>) {
-    let remote_addr = stream.get_ref().local_addr().unwrap();
-    let mut success: bool = false;
-    let mut headers_read: bool = false;
-    let mut content_length: Option<usize> = None;
-    let mut rtc_url_match = false;
-    let mut body: Vec<u8> = Vec::new();
-
-    let buf_reader = BufReader::new(stream.clone());
-    let mut bytes = buf_reader.bytes();
-    {
-        let mut line: Vec<u8> = Vec::new();
-        while let Some(byte) = bytes.next().await {
-            let byte = byte.unwrap();
-
-            if headers_read {
-                if let Some(content_length) = content_length {
-                    body.push(byte);
-
-                    if body.len() >= content_length {
-                        success = true;
-                        break;
-                    }
-                } else {
-                    info!("request was missing Content-Length header");
-                    break;
-                }
-            }
+async fn serve(
+    routes: SharedSessionRoutes,
+    remote_addr: SocketAddr,
+    mut stream: Arc<Async<TcpStream>>,
+    permit: ConnectionPermit,
+) {
+    let limiter = &permit.0;
+    let timeout = limiter.config.request_timeout;
+    let mut buf_reader = BufReader::new(stream.clone());
+
+    let read = read_request(&mut buf_reader, remote_addr, &limiter.config);
+    let timer = async {
+        Timer::after(timeout).await;
+        Err(RequestError::Timeout)
+    };
+
+    let response = match future::or(read, timer).await {
+        Ok(request) => respond(&routes, limiter, request).await,
+        Err(err) => {
+            info!(
+                "Rejected HTTP request from {} with status {}",
+                remote_addr,
+                err.status()
+            );
+            empty_response(err.status())
+        }
+    };
 
-            if byte == b'\r' {
-                continue;
-            } else if byte == b'\n' {
-                let mut str = String::from_utf8(line.clone()).unwrap();
-                line.clear();
-
-                if rtc_url_match {
-                    if str.to_lowercase().starts_with("content-length: ") {
-                        let (_, last) = str.split_at(16);
-                        str = last.to_string();
-                        content_length = str.parse::<usize>().ok();
-                    } else if str.len() == 0 {
-                        headers_read = true;
-                    }
-                } else if str.starts_with(RTC_URL_PATH.get().unwrap()) {
-                    rtc_url_match = true;
-                }
-            } else {
-                line.push(byte);
-            }
+    let _ = write_response(&mut stream, response, timeout).await;
+    let _ = stream.close().await;
+}
+
+/// Reads the request line, headers & body of an HTTP request, enforcing the
+/// size limits of the given SignalingConfig
+async fn read_request<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    remote_addr: SocketAddr,
+    config: &SignalingConfig,
+) -> Result<HttpRequest, RequestError> {
+    let mut header_budget = config.max_header_size;
+
+    let request_line = read_header_line(reader, &mut header_budget).await?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().ok_or(RequestError::Malformed)?.to_uppercase();
+    let target = parts.next().ok_or(RequestError::Malformed)?;
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), Some(query.to_string())),
+        None => (target.to_string(), None),
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_header_line(reader, &mut header_budget).await?;
+        if line.is_empty() {
+            break;
         }
+        let (name, value) = line.split_once(':').ok_or(RequestError::Malformed)?;
+        headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+    }
 
-        if success {
-            success = false;
+    let content_length = match headers.iter().find(|(name, _)| name == "content-length") {
+        Some((_, value)) => value
+            .parse::<usize>()
+            .map_err(|_| RequestError::Malformed)?,
+        None => 0,
+    };
+    if content_length > config.max_body_size {
+        return Err(RequestError::BodyTooLarge);
+    }
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .await
+        .map_err(|_| RequestError::Malformed)?;
+
+    Ok(HttpRequest {
+        method,
+        path,
+        query,
+        headers,
+        body,
+        remote_addr,
+    })
+}
 
-            let mut lines = body.lines();
-            let buf = RequestBuffer::new(&mut lines);
+/// Reads a single line of the request head, without its line ending, never
+/// reading more than the remaining header budget
+async fn read_header_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    header_budget: &mut usize,
+) -> Result<String, RequestError> {
+    let mut line = Vec::new();
+    let read = reader
+        .take(*header_budget as u64)
+        .read_until(b'\n', &mut line)
+        .await
+        .map_err(|_| RequestError::Malformed)?;
+
+    if !line.ends_with(b"\n") {
+        return Err(if read == *header_budget {
+            RequestError::HeaderTooLarge
+        } else {
+            // connection closed before the end of the headers
+            RequestError::Malformed
+        });
+    }
+    *header_budget -= read;
 
-            match session_endpoint.http_session_request(buf).await {
-                Ok(mut resp) => {
-                    success = true;
+    let line = String::from_utf8(line).map_err(|_| RequestError::Malformed)?;
+    Ok(line.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
 
-                    resp.headers_mut().insert(
-                        header::ACCESS_CONTROL_ALLOW_ORIGIN,
-                        HeaderValue::from_static("*"),
-                    );
+/// Dispatches a request to the matching RTC session endpoint or HttpRoute
+async fn respond(
+    routes: &SharedSessionRoutes,
+    limiter: &SignalingLimiter,
+    request: HttpRequest,
+) -> Response<Vec<u8>> {
+    let (session_endpoint, http_route) = {
+        let routes = routes.lock().unwrap();
+        let session_endpoint = if request.method == "POST" {
+            routes.session_endpoint(&request.path)
+        } else {
+            None
+        };
+        (
+            session_endpoint,
+            routes.http_route(&request.method, &request.path),
+        )
+    };
+
+    if let Some((mut session_endpoint, ip_filter)) = session_endpoint {
+        if !ip_filter.is_allowed(&request.remote_addr.ip()) {
+            info!(
+                "Refused WebRTC session request from filtered address {}",
+                request.remote_addr
+            );
+            return empty_response(StatusCode::FORBIDDEN);
+        }
 
-                    let mut out = response_header_to_vec(&resp);
-                    out.extend_from_slice(resp.body().as_bytes());
+        if !limiter.allow_session_request(request.remote_addr.ip()) {
+            info!(
+                "Too many WebRTC session requests from {}",
+                request.remote_addr
+            );
+            let mut response = empty_response(StatusCode::TOO_MANY_REQUESTS);
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(limiter.config.session_rate_window.as_secs()),
+            );
+            return response;
+        }
 
-                    info!("Successful WebRTC session request from {}", remote_addr);
+        let mut lines = request.body.lines();
+        let buf = RequestBuffer::new(&mut lines);
 
-                    stream.write_all(&out).await.unwrap();
-                }
-                Err(err) => {
-                    info!(
-                        "Invalid WebRTC session request from {}. Error: {}",
-                        remote_addr, err
-                    );
-                }
+        return match session_endpoint.http_session_request(buf).await {
+            Ok(resp) => {
+                info!(
+                    "Successful WebRTC session request from {}",
+                    request.remote_addr
+                );
+                resp.map(String::into_bytes)
             }
-        }
+            Err(err) => {
+                info!(
+                    "Invalid WebRTC session request from {}. Error: {}",
+                    request.remote_addr, err
+                );
+                empty_response(StatusCode::BAD_REQUEST)
+            }
+        };
     }
 
-    if !success {
-        stream.write_all(RESPONSE_BAD).await.unwrap();
+    if let Some(http_route) = http_route {
+        // Handlers are free to block, e.g. to read files from disk
+        let response = smol::unblock(move || http_route.handle(&request)).await;
+        return to_http_response(response);
     }
 
-    stream.flush().await.unwrap();
-    stream.close().await.unwrap();
+    empty_response(StatusCode::NOT_FOUND)
 }
 
-const RESPONSE_BAD: &[u8] = br#"
-HTTP/1.1 404 NOT FOUND
-Content-Type: text/html
-Content-Length: 0
-Access-Control-Allow-Origin: *
-"#;
+fn empty_response(status: StatusCode) -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = status;
+    response
+}
+
+fn to_http_response(response: HttpResponse) -> Response<Vec<u8>> {
+    let mut builder = Response::builder().status(response.status);
+    for (name, value) in &response.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    match builder.body(response.body) {
+        Ok(response) => response,
+        Err(err) => {
+            warn!("HTTP route produced an invalid response: {}", err);
+            empty_response(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Writes a response to the client, giving up if the client doesn't accept it
+/// within the given timeout
+async fn write_response(
+    stream: &mut Arc<Async<TcpStream>>,
+    response: Response<Vec<u8>>,
+    timeout: Duration,
+) -> std::io::Result<()> {
+    let out = response_to_vec(response);
+
+    let write = async {
+        stream.write_all(&out).await?;
+        stream.flush().await
+    };
+    let timer = async {
+        Timer::after(timeout).await;
+        Err(ErrorKind::TimedOut.into())
+    };
+    future::or(write, timer).await
+}
+
+/// Writes out a complete response, with the headers every response carries
+fn response_to_vec(mut response: Response<Vec<u8>>) -> Vec<u8> {
+    let content_length = response.body().len();
+    let headers = response.headers_mut();
+    headers.insert(
+        header::ACCESS_CONTROL_ALLOW_ORIGIN,
+        HeaderValue::from_static("*"),
+    );
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(content_length));
+    headers.insert(header::CONNECTION, HeaderValue::from_static("close"));
+
+    let mut out = response_header_to_vec(&response);
+    out.extend_from_slice(response.body());
+    out
+}
 
 struct RequestBuffer<'a, R: AsyncBufRead + Unpin> {
     buffer: &'a mut Lines<R>,
@@ -220,10 +656,7 @@ fn response_header_to_vec<T>(r: &Response<T>) -> Vec<u8> {
     c.into_inner()
 }
 
-fn write_response_header<T>(
-    r: &Response<T>,
-    mut io: impl std::io::Write,
-) -> std::io::Result<usize> {
+fn write_response_header<T>(r: &Response<T>, mut io: impl Write) -> std::io::Result<usize> {
     let mut len = 0;
     macro_rules! w {
         ($x:expr) => {
@@ -253,3 +686,160 @@ fn write_response_header<T>(
     w!(b"\r\n");
     Ok(len)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{IpAddr, SocketAddr, TcpStream},
+        time::Duration,
+    };
+
+    use webrtc_unreliable::Server as InnerRtcServer;
+
+    use naia_socket_shared::{ManualClock, RealClock, SignalingConfig, SocketConfig};
+
+    use super::{start_session_server, SessionRegistration, SignalingLimiter, MAX_TRACKED_IPS};
+    use crate::server_addrs::ServerAddrs;
+
+    fn signaling_server(signaling: SignalingConfig) -> (InnerRtcServer, SessionRegistration) {
+        let any_port: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let rtc_server = smol::block_on(InnerRtcServer::new(any_port, any_port)).unwrap();
+        let config = SocketConfig {
+            signaling,
+            ..Default::default()
+        };
+        let registration = start_session_server(
+            ServerAddrs::new(any_port, any_port, "http://127.0.0.1:0"),
+            config,
+            rtc_server.session_endpoint(),
+            Vec::new(),
+            std::sync::Arc::new(RealClock),
+        )
+        .unwrap();
+        (rtc_server, registration)
+    }
+
+    // Sends the given bytes & returns the status line of the response
+    fn request(registration: &SessionRegistration, bytes: &[u8]) -> String {
+        let mut stream = TcpStream::connect(registration.listen_addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream.write_all(bytes).unwrap();
+        status_line(&mut stream)
+    }
+
+    fn status_line(stream: &mut TcpStream) -> String {
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response);
+        let response = String::from_utf8_lossy(&response);
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[test]
+    fn enforces_size_limits_and_timeout() {
+        let (_rtc_server, registration) = signaling_server(SignalingConfig {
+            request_timeout: Duration::from_millis(100),
+            max_header_size: 256,
+            max_body_size: 16,
+            ..Default::default()
+        });
+
+        let long_header = format!("GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "a".repeat(512));
+        assert_eq!(
+            request(&registration, long_header.as_bytes()),
+            "HTTP/1.1 431 Request Header Fields Too Large"
+        );
+        assert_eq!(
+            request(
+                &registration,
+                b"POST / HTTP/1.1\r\nContent-Length: 17\r\n\r\n"
+            ),
+            "HTTP/1.1 413 Payload Too Large"
+        );
+        assert_eq!(
+            request(&registration, b"GET / HTTP/1.1\r\n"),
+            "HTTP/1.1 408 Request Timeout"
+        );
+        assert_eq!(
+            request(&registration, b"GET / HTTP/1.1\r\n\r\n"),
+            "HTTP/1.1 404 Not Found"
+        );
+    }
+
+    #[test]
+    fn limits_session_requests_and_connections() {
+        let (_rtc_server, registration) = signaling_server(SignalingConfig {
+            request_timeout: Duration::from_secs(2),
+            max_connections: 1,
+            max_session_requests_per_ip: Some(1),
+            ..Default::default()
+        });
+
+        let session_request = b"POST /rtc_session HTTP/1.1\r\nContent-Length: 4\r\n\r\nnope";
+        assert_eq!(
+            request(&registration, session_request),
+            "HTTP/1.1 400 Bad Request"
+        );
+        assert_eq!(
+            request(&registration, session_request),
+            "HTTP/1.1 429 Too Many Requests"
+        );
+
+        // The first connection holds the only slot while it sends nothing
+        let _idle = TcpStream::connect(registration.listen_addr).unwrap();
+        assert_eq!(
+            request(&registration, b""),
+            "HTTP/1.1 503 Service Unavailable"
+        );
+    }
+
+    #[test]
+    fn caps_the_addresses_tracked_for_session_requests() {
+        let limiter = SignalingLimiter::new(
+            SignalingConfig {
+                max_session_requests_per_ip: Some(1),
+                ..Default::default()
+            },
+            std::sync::Arc::new(RealClock),
+        );
+
+        for n in 0..MAX_TRACKED_IPS as u32 {
+            assert!(limiter.allow_session_request(IpAddr::from(n.to_be_bytes())));
+        }
+        assert_eq!(
+            limiter.session_requests.lock().unwrap().windows.len(),
+            MAX_TRACKED_IPS
+        );
+
+        // New addresses are refused, while those already counted are limited
+        // as usual
+        let new_ip = IpAddr::from([10, 0, 0, 1]);
+        assert!(!limiter.allow_session_request(new_ip));
+        assert!(!limiter.allow_session_request(IpAddr::from([0, 0, 0, 0])));
+        assert_eq!(
+            limiter.session_requests.lock().unwrap().windows.len(),
+            MAX_TRACKED_IPS
+        );
+    }
+
+    #[test]
+    fn session_request_windows_follow_the_clock() {
+        let clock = std::sync::Arc::new(ManualClock::new());
+        let limiter = SignalingLimiter::new(
+            SignalingConfig {
+                max_session_requests_per_ip: Some(1),
+                session_rate_window: Duration::from_secs(10),
+                ..Default::default()
+            },
+            clock.clone(),
+        );
+        let ip = IpAddr::from([10, 0, 0, 1]);
+
+        assert!(limiter.allow_session_request(ip));
+        assert!(!limiter.allow_session_request(ip));
+        clock.advance(Duration::from_secs(10));
+        assert!(limiter.allow_session_request(ip));
+    }
+}
@@ -1,18 +1,31 @@
-use std::{io::Error as IoError, net::SocketAddr};
+use std::{
+    collections::HashSet,
+    io::Error as IoError,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
 use futures_channel::mpsc;
 use futures_util::{pin_mut, select, FutureExt, StreamExt};
+use log::warn;
+use smol::Timer;
 use webrtc_unreliable::{
     MessageResult, MessageType, SendError, Server as InnerRtcServer, SessionEndpoint,
 };
 
-use naia_socket_shared::{parse_server_url, url_to_socket_addr, SocketConfig};
+use naia_socket_shared::{parse_server_url, url_to_socket_addr, DisconnectReason, SocketConfig};
 
-use crate::{error::NaiaServerSocketError, server_addrs::ServerAddrs};
+use crate::{
+    client_info::Transport, connections::Connections, error::NaiaServerSocketError,
+    http_route::HttpRoute, server_addrs::ServerAddrs,
+};
 
-use super::session::start_session_server;
+use super::session::{start_session_server, SessionRegistration};
 
 const CLIENT_CHANNEL_SIZE: usize = 8;
+// How often the established WebRTC sessions are checked for ones which have
+// opened or closed
+const SESSION_CHECK_INTERVAL: Duration = Duration::from_millis(500);
 
 /// A socket which communicates with clients using an underlying
 /// unordered & unreliable network protocol
@@ -21,34 +34,56 @@ pub struct Socket {
     rtc_server: RtcServer,
     to_client_sender: mpsc::Sender<(SocketAddr, Box<[u8]>)>,
     to_client_receiver: mpsc::Receiver<(SocketAddr, Box<[u8]>)>,
+    connections: Connections,
+    sessions: HashSet<SocketAddr>,
+    next_session_check: Instant,
+    _session_registration: SessionRegistration,
 }
 
 impl Socket {
-    /// Returns a new ServerSocket, listening at the given socket address
-    pub async fn listen(server_addrs: ServerAddrs, config: SocketConfig) -> Self {
+    /// What Clients are connected over
+    pub const TRANSPORT: Transport = Transport::WebRtc;
+
+    /// Returns a new ServerSocket, listening at the given socket address, or
+    /// an error if it can't be bound or its RTC endpoint path is in use
+    pub async fn listen(
+        server_addrs: ServerAddrs,
+        config: SocketConfig,
+        http_routes: Vec<HttpRoute>,
+        connections: Connections,
+    ) -> Result<Self, NaiaServerSocketError> {
         let (to_client_sender, to_client_receiver) = mpsc::channel(CLIENT_CHANNEL_SIZE);
 
         let rtc_server = RtcServer::new(
             server_addrs.webrtc_listen_addr,
             url_to_socket_addr(&parse_server_url(&server_addrs.public_webrtc_url)),
         )
-        .await;
+        .await?;
+
+        let session_registration = start_session_server(
+            server_addrs,
+            config,
+            rtc_server.session_endpoint(),
+            http_routes,
+            connections.clock().clone(),
+        )?;
 
-        let socket = Socket {
+        Ok(Socket {
             rtc_server,
             to_client_sender,
             to_client_receiver,
-        };
-
-        start_session_server(server_addrs, config, socket.rtc_server.session_endpoint());
-
-        socket
+            connections,
+            sessions: HashSet::new(),
+            next_session_check: Instant::now() + SESSION_CHECK_INTERVAL,
+            _session_registration: session_registration,
+        })
     }
 
     pub async fn receive(&mut self) -> Result<(SocketAddr, Box<[u8]>), NaiaServerSocketError> {
         enum Next {
             FromClientMessage(Result<(SocketAddr, Box<[u8]>), IoError>),
             ToClientMessage((SocketAddr, Box<[u8]>)),
+            CheckSessions,
         }
 
         loop {
@@ -60,6 +95,9 @@ impl Socket {
                 let from_client_message_receiver_next = rtc_server.recv().fuse();
                 pin_mut!(from_client_message_receiver_next);
 
+                let session_check = FutureExt::fuse(Timer::at(self.next_session_check));
+                pin_mut!(session_check);
+
                 select! {
                     from_client_result = from_client_message_receiver_next => {
                         Next::FromClientMessage(
@@ -76,6 +114,9 @@ impl Socket {
                             to_client_message.expect("to server message receiver closed")
                         )
                     }
+                    _ = session_check => {
+                        Next::CheckSessions
+                    }
                 }
             };
 
@@ -100,8 +141,43 @@ impl Socket {
                         _ => {}
                     }
                 }
+                Next::CheckSessions => self.check_sessions().await,
+            }
+        }
+    }
+
+    // Connects Clients whose WebRTC sessions have been established,
+    // disconnects those whose sessions have closed, & closes the sessions of
+    // Clients which have been kicked
+    async fn check_sessions(&mut self) {
+        self.next_session_check = Instant::now() + SESSION_CHECK_INTERVAL;
+
+        let mut sessions = HashSet::new();
+        let mut kicked = Vec::new();
+        for address in self.rtc_server.connected_clients() {
+            if self.connections.is_kicked(&address) {
+                kicked.push(address);
+            } else {
+                sessions.insert(address);
+            }
+        }
+        for address in kicked {
+            if let Err(err) = self.rtc_server.disconnect(&address).await {
+                warn!(
+                    "Error closing the session of kicked Client {}: {}",
+                    address, err
+                );
             }
         }
+
+        for address in sessions.difference(&self.sessions) {
+            self.connections.connect(address);
+        }
+        for address in self.sessions.difference(&sessions) {
+            self.connections
+                .disconnect(address, DisconnectReason::Closed);
+        }
+        self.sessions = sessions;
     }
 
     pub fn sender(&self) -> mpsc::Sender<(SocketAddr, Box<[u8]>)> {
@@ -114,18 +190,29 @@ struct RtcServer {
 }
 
 impl RtcServer {
-    pub async fn new(listen_addr: SocketAddr, public_address: SocketAddr) -> RtcServer {
+    pub async fn new(
+        listen_addr: SocketAddr,
+        public_address: SocketAddr,
+    ) -> Result<RtcServer, NaiaServerSocketError> {
         let inner = InnerRtcServer::new(listen_addr, public_address)
             .await
-            .expect("could not start RTC server");
+            .map_err(|err| NaiaServerSocketError::Wrapped(Box::new(err)))?;
 
-        return RtcServer { inner };
+        return Ok(RtcServer { inner });
     }
 
     pub fn session_endpoint(&self) -> SessionEndpoint {
         self.inner.session_endpoint()
     }
 
+    pub fn connected_clients(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        self.inner.connected_clients().copied()
+    }
+
+    pub async fn disconnect(&mut self, remote_addr: &SocketAddr) -> Result<(), IoError> {
+        self.inner.disconnect(remote_addr).await
+    }
+
     pub async fn recv(&mut self) -> Result<MessageResult<'_>, IoError> {
         self.inner.recv().await
     }
@@ -1,4 +1,7 @@
-use super::{packet_receiver::PacketReceiver, packet_sender::PacketSender};
+use super::{
+    connections::Connections, event_receiver::EventReceiver, packet_receiver::PacketReceiver,
+    packet_sender::PacketSender,
+};
 
 /// Contains internal socket packet sender/receiver
 pub(crate) struct Io {
@@ -6,4 +9,8 @@ pub(crate) struct Io {
     pub packet_sender: PacketSender,
     /// Used to receive packets from the socket
     pub packet_receiver: PacketReceiver,
+    /// Used to receive events from the socket
+    pub event_receiver: EventReceiver,
+    /// Keeps track of the connected Clients
+    pub connections: Connections,
 }
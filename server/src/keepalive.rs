@@ -0,0 +1,69 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use naia_socket_shared::{ControlPacket, DisconnectReason, KeepaliveConfig, Timer};
+
+use super::{connections::Connections, packet_sender::PacketSender};
+
+/// Sends heartbeats to the connected Clients, & disconnects them when they go
+/// quiet or say goodbye. Clones share the same heartbeat timer
+#[derive(Clone)]
+pub struct Keepalive {
+    config: KeepaliveConfig,
+    epoch: u64,
+    heartbeat_timer: Arc<Mutex<Timer>>,
+    connections: Connections,
+}
+
+impl Keepalive {
+    /// Creates a new Keepalive, whose heartbeats carry the given epoch
+    pub fn new(config: KeepaliveConfig, epoch: u64, connections: &Connections) -> Self {
+        Keepalive {
+            heartbeat_timer: Arc::new(Mutex::new(Timer::with_clock(
+                config.heartbeat_interval,
+                connections.clock().clone(),
+            ))),
+            config,
+            epoch,
+            connections: connections.clone(),
+        }
+    }
+
+    /// Disconnects the Clients which have been quiet for the idle timeout, &
+    /// sends heartbeats to the rest if they are due
+    pub fn poll(&self, packet_sender: &PacketSender) {
+        self.connections.disconnect_idle(self.config.idle_timeout);
+
+        {
+            let mut heartbeat_timer = self.heartbeat_timer.lock().unwrap();
+            if !heartbeat_timer.ringing() {
+                return;
+            }
+            heartbeat_timer.reset();
+        }
+        let heartbeat = ControlPacket::Heartbeat {
+            epoch: Some(self.epoch),
+        }
+        .to_bytes();
+        for address in self.connections.addresses() {
+            packet_sender.send(&address, &heartbeat);
+        }
+    }
+
+    /// Records that the Client at the given address said goodbye
+    pub fn goodbye(&self, address: &SocketAddr) {
+        self.connections
+            .disconnect(address, DisconnectReason::Goodbye);
+    }
+
+    /// Says goodbye to every Client, & forgets them
+    pub fn shutdown(&self, packet_sender: &PacketSender) {
+        let goodbye = ControlPacket::Goodbye.to_bytes();
+        for address in self.connections.addresses() {
+            packet_sender.send(&address, &goodbye);
+        }
+        self.connections.clear();
+    }
+}
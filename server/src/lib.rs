@@ -16,23 +16,37 @@ extern crate log;
 #[macro_use]
 extern crate cfg_if;
 
+mod client_info;
 mod conditioned_packet_receiver;
+mod connections;
+mod control_packet_receiver;
 mod error;
+mod event_receiver;
+mod http_route;
 mod impls;
 mod io;
+mod keepalive;
+mod link_conditioner_profiles;
 mod packet_receiver;
 mod packet_sender;
+mod rate_limiter;
 mod server_addrs;
+mod server_event;
 mod socket;
 
 /// Executor for Server
 pub mod executor;
 
+pub use client_info::{ClientInfo, Transport};
 pub use error::NaiaServerSocketError;
+pub use event_receiver::EventReceiver;
+pub use http_route::{HttpRequest, HttpResponse, HttpRoute, RouteHandler};
+pub use link_conditioner_profiles::LinkConditionerProfiles;
 pub use naia_socket_shared as shared;
 pub use packet_receiver::PacketReceiver;
 pub use packet_sender::PacketSender;
 pub use server_addrs::ServerAddrs;
+pub use server_event::ServerEvent;
 pub use socket::Socket;
 
 cfg_if! {
@@ -0,0 +1,390 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant as StdInstant},
+};
+
+use log::info;
+
+use naia_socket_shared::{
+    Instant, LinkConditioner, LinkConditionerConfig, LinkConditionerHandle, LinkDirection,
+    SeededRandom,
+};
+
+use super::connections::Connections;
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+// A Client's LinkConditioner is forgotten once it has nothing queued & has had
+// no packets for this long. Until then its schedule, loss state & random
+// sequence carry on, however quiet the Client is
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+type Packet = (SocketAddr, Box<[u8]>);
+
+/// The LinkConditionerConfig applied to each remote address, with a default
+/// for addresses which have no profile of their own. Clones share the same
+/// profiles, so they can be updated at runtime through any clone.
+///
+/// A profile can also be set for an identity, which applies to whichever
+/// address a Client authenticated as that identity with `Socket.set_identity()`
+/// is connected from, so it follows the Client when it reconnects or its
+/// address changes. An identity's profile takes precedence over the profile
+/// of the address
+#[derive(Clone)]
+pub struct LinkConditionerProfiles {
+    handle: LinkConditionerHandle,
+    by_address: Arc<RwLock<HashMap<SocketAddr, LinkConditionerConfig>>>,
+    by_identity: Arc<RwLock<HashMap<String, LinkConditionerConfig>>>,
+    // The Socket's Clients, whose identities are looked up once it listens
+    connections: Arc<RwLock<Option<Connections>>>,
+    generation: Arc<AtomicU64>,
+}
+
+impl LinkConditionerProfiles {
+    /// Creates a new LinkConditionerProfiles, which applies the given config
+    /// to every address
+    pub fn new(default: LinkConditionerConfig) -> Self {
+        LinkConditionerProfiles {
+            handle: LinkConditionerHandle::new(default),
+            by_address: Arc::new(RwLock::new(HashMap::new())),
+            by_identity: Arc::new(RwLock::new(HashMap::new())),
+            connections: Arc::new(RwLock::new(None)),
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Gets the LinkConditionerHandle which holds the default config, & which
+    /// collects the statistics of every address
+    pub fn handle(&self) -> LinkConditionerHandle {
+        self.handle.clone()
+    }
+
+    /// Replaces the config applied to addresses without a profile
+    pub fn set_default(&self, config: LinkConditionerConfig) {
+        self.handle.set_config(config);
+    }
+
+    /// Gets the config applied to addresses without a profile
+    pub fn default_config(&self) -> LinkConditionerConfig {
+        self.handle.config()
+    }
+
+    /// Applies the given config to packets to & from the given address
+    pub fn set_profile(&self, address: SocketAddr, config: LinkConditionerConfig) {
+        if let Some(seed) = config.seed {
+            info!("link conditioner: seed for {} is {}", address, seed);
+        }
+        self.by_address.write().unwrap().insert(address, config);
+        self.changed();
+    }
+
+    /// Removes the profile of the given address, which then falls back to the
+    /// default config
+    pub fn remove_profile(&self, address: &SocketAddr) -> Option<LinkConditionerConfig> {
+        let removed = self.by_address.write().unwrap().remove(address);
+        self.changed();
+        removed
+    }
+
+    /// Gets the profile of the given address, if it has one
+    pub fn profile(&self, address: &SocketAddr) -> Option<LinkConditionerConfig> {
+        self.by_address.read().unwrap().get(address).cloned()
+    }
+
+    /// Applies the given config to packets to & from the Client authenticated
+    /// as the given identity, wherever it is connected from
+    pub fn set_identity_profile(&self, identity: impl Into<String>, config: LinkConditionerConfig) {
+        let identity = identity.into();
+        if let Some(seed) = config.seed {
+            info!("link conditioner: seed for '{}' is {}", identity, seed);
+        }
+        self.by_identity.write().unwrap().insert(identity, config);
+        self.changed();
+    }
+
+    /// Removes the profile of the given identity, whose Client then falls back
+    /// to the profile of its address, or the default config
+    pub fn remove_identity_profile(&self, identity: &str) -> Option<LinkConditionerConfig> {
+        let removed = self.by_identity.write().unwrap().remove(identity);
+        self.changed();
+        removed
+    }
+
+    /// Gets the profile of the given identity, if it has one
+    pub fn identity_profile(&self, identity: &str) -> Option<LinkConditionerConfig> {
+        self.by_identity.read().unwrap().get(identity).cloned()
+    }
+
+    /// Gets the config applied to the given address, which is the profile of
+    /// the identity its Client has been authenticated as, or else the profile
+    /// of the address, or else the default
+    pub fn config_for(&self, address: &SocketAddr) -> LinkConditionerConfig {
+        self.identity_of(address)
+            .and_then(|identity| self.identity_profile(&identity))
+            .or_else(|| self.profile(address))
+            .unwrap_or_else(|| self.handle.config())
+    }
+
+    /// Looks up identities in the given Connections, re-resolving each
+    /// Client's profile whenever it is given one with `identity_changed()`
+    pub(crate) fn attach(&self, connections: &Connections) {
+        *self.connections.write().unwrap() = Some(connections.clone());
+        self.changed();
+    }
+
+    /// Re-resolves the profile of each Client, after one has been given an
+    /// identity
+    pub(crate) fn identity_changed(&self) {
+        self.changed();
+    }
+
+    fn identity_of(&self, address: &SocketAddr) -> Option<String> {
+        self.connections
+            .read()
+            .unwrap()
+            .as_ref()?
+            .get(address)?
+            .identity
+    }
+
+    // Changes whenever a profile or the default config changes
+    fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire) + self.handle.generation()
+    }
+
+    fn changed(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// A LinkConditioner for each remote address, configured by
+/// LinkConditionerProfiles
+#[derive(Clone)]
+pub(crate) struct ClientConditioners {
+    profiles: LinkConditionerProfiles,
+    direction: LinkDirection,
+    generation: u64,
+    conditioners: HashMap<SocketAddr, ClientConditioner>,
+    unconditioned: VecDeque<Packet>,
+    last_prune: StdInstant,
+}
+
+#[derive(Clone)]
+struct ClientConditioner {
+    conditioner: LinkConditioner<Packet>,
+    last_packet: StdInstant,
+}
+
+impl ClientConditioners {
+    /// Creates a new ClientConditioners for packets travelling in the given
+    /// direction
+    pub fn new(profiles: LinkConditionerProfiles, direction: LinkDirection) -> Self {
+        ClientConditioners {
+            generation: profiles.generation(),
+            profiles,
+            direction,
+            conditioners: HashMap::new(),
+            unconditioned: VecDeque::new(),
+            last_prune: StdInstant::now(),
+        }
+    }
+
+    /// Process a packet with the LinkConditioner of its address
+    pub fn process_packet(&mut self, packet: Packet) {
+        self.refresh_configs();
+
+        let address = packet.0;
+        if let Some(client) = self.conditioners.get_mut(&address) {
+            client.last_packet = StdInstant::now();
+            client.conditioner.process_packet(packet);
+            return;
+        }
+
+        let config = self.config_for(&address);
+        if self.direction == LinkDirection::Outgoing && !config.conditions_outgoing() {
+            self.unconditioned.push_back(packet);
+            return;
+        }
+        let mut conditioner =
+            LinkConditioner::new(&config, self.direction).reporting_to(&self.profiles.handle);
+        conditioner.process_packet(packet);
+        self.conditioners.insert(
+            address,
+            ClientConditioner {
+                conditioner,
+                last_packet: StdInstant::now(),
+            },
+        );
+    }
+
+    /// Pops a packet which is ready to be delivered, if any
+    pub fn pop_item(&mut self) -> Option<Packet> {
+        if let Some(packet) = self.unconditioned.pop_front() {
+            return Some(packet);
+        }
+
+        let now = StdInstant::now();
+        if now.duration_since(self.last_prune) >= PRUNE_INTERVAL {
+            self.conditioners.retain(|_, client| {
+                !client.conditioner.is_empty()
+                    || now.duration_since(client.last_packet) < IDLE_TIMEOUT
+            });
+            self.last_prune = now;
+        }
+
+        self.conditioners
+            .values_mut()
+            .find(|client| client.conditioner.has_item())
+            .and_then(|client| client.conditioner.pop_item())
+    }
+
+    /// Returns the Instant at which the next packet will be ready to be
+    /// delivered, if any are queued
+    pub fn next_deadline(&self) -> Option<Instant> {
+        if !self.unconditioned.is_empty() {
+            return Some(Instant::now());
+        }
+        self.conditioners
+            .values()
+            .filter_map(|client| client.conditioner.next_deadline())
+            .min()
+    }
+
+    // Apply any profiles which have changed since the last packet
+    fn refresh_configs(&mut self) {
+        let generation = self.profiles.generation();
+        if generation == self.generation {
+            return;
+        }
+        self.generation = generation;
+        let addresses: Vec<SocketAddr> = self.conditioners.keys().copied().collect();
+        for address in addresses {
+            let config = self.config_for(&address);
+            if let Some(client) = self.conditioners.get_mut(&address) {
+                client.conditioner.set_config(&config);
+            }
+        }
+    }
+
+    // The config applied to the given address, with its seed, if any, mixed
+    // with the address so that each Client gets its own sequence
+    fn config_for(&self, address: &SocketAddr) -> LinkConditionerConfig {
+        let mut config = self.profiles.config_for(address);
+        config.seed = config.seed.map(|seed| seed_for(seed, address));
+        config
+    }
+}
+
+fn seed_for(seed: u64, address: &SocketAddr) -> u64 {
+    let ip = match address.ip() {
+        IpAddr::V4(ip) => u128::from(u32::from(ip)),
+        IpAddr::V6(ip) => u128::from(ip),
+    };
+    [ip as u64, (ip >> 64) as u64, u64::from(address.port())]
+        .iter()
+        .fold(seed, |seed, word| SeededRandom::new(seed ^ word).next_u64())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use crossbeam::channel;
+
+    use naia_socket_shared::{LinkConditionerConfig, LinkDirection};
+
+    use super::{ClientConditioners, LinkConditionerProfiles};
+    use crate::{client_info::Transport, connections::Connections};
+
+    fn payload(byte: u8) -> Box<[u8]> {
+        vec![byte].into_boxed_slice()
+    }
+
+    #[test]
+    fn profiles_override_the_default_at_runtime() {
+        let lossy: SocketAddr = "10.0.0.1:1000".parse().unwrap();
+        let clean: SocketAddr = "10.0.0.2:1000".parse().unwrap();
+        let profiles = LinkConditionerProfiles::new(LinkConditionerConfig::default());
+        let mut conditioners = ClientConditioners::new(profiles.clone(), LinkDirection::Incoming);
+
+        profiles.set_profile(lossy, LinkConditionerConfig::new(0, 0, 1.0));
+        conditioners.process_packet((lossy, payload(1)));
+        conditioners.process_packet((clean, payload(2)));
+        assert_eq!(conditioners.pop_item(), Some((clean, payload(2))));
+        assert_eq!(conditioners.pop_item(), None);
+
+        profiles.remove_profile(&lossy);
+        conditioners.process_packet((lossy, payload(3)));
+        assert_eq!(conditioners.pop_item(), Some((lossy, payload(3))));
+    }
+
+    #[test]
+    fn seeds_each_client_differently() {
+        let first: SocketAddr = "10.0.0.1:1000".parse().unwrap();
+        let second: SocketAddr = "10.0.0.1:1001".parse().unwrap();
+        let config = LinkConditionerConfig::new(0, 0, 0.5).with_seed(42);
+        let delivered = |address: SocketAddr| {
+            let profiles = LinkConditionerProfiles::new(config.clone());
+            let mut conditioners = ClientConditioners::new(profiles, LinkDirection::Incoming);
+            for byte in 0..64 {
+                conditioners.process_packet((address, payload(byte)));
+            }
+            let mut delivered = Vec::new();
+            while let Some((_, payload)) = conditioners.pop_item() {
+                delivered.push(payload[0]);
+            }
+            delivered
+        };
+
+        assert_eq!(delivered(first), delivered(first));
+        assert_ne!(delivered(first), delivered(second));
+    }
+
+    #[test]
+    fn identity_profiles_follow_the_client() {
+        let first: SocketAddr = "10.0.0.1:1000".parse().unwrap();
+        let second: SocketAddr = "10.0.0.2:2000".parse().unwrap();
+        let (event_sender, _event_receiver) = channel::unbounded();
+        let connections = Connections::new(Transport::Udp, event_sender);
+        let profiles = LinkConditionerProfiles::new(LinkConditionerConfig::default());
+        profiles.attach(&connections);
+        let mut conditioners = ClientConditioners::new(profiles.clone(), LinkDirection::Incoming);
+        profiles.set_identity_profile("player", LinkConditionerConfig::new(0, 0, 1.0));
+
+        connections.received(&first, 1);
+        conditioners.process_packet((first, payload(1)));
+        assert_eq!(conditioners.pop_item(), Some((first, payload(1))));
+
+        // Once authenticated, the Client gets its identity's profile
+        connections.set_identity(&first, "player".to_string());
+        profiles.identity_changed();
+        conditioners.process_packet((first, payload(2)));
+        assert_eq!(conditioners.pop_item(), None);
+
+        // & keeps it when it reconnects from another address
+        connections.received(&second, 1);
+        connections.set_identity(&second, "player".to_string());
+        profiles.identity_changed();
+        conditioners.process_packet((second, payload(3)));
+        assert_eq!(conditioners.pop_item(), None);
+    }
+
+    #[test]
+    fn forgets_the_queued_packets_of_dropped_clients() {
+        let address: SocketAddr = "10.0.0.1:1000".parse().unwrap();
+        let profiles = LinkConditionerProfiles::new(LinkConditionerConfig::new(1000, 0, 0.0));
+        let mut conditioners = ClientConditioners::new(profiles.clone(), LinkDirection::Incoming);
+        for byte in 0..3 {
+            conditioners.process_packet((address, payload(byte)));
+        }
+        let queue_depth = || profiles.handle().stats(LinkDirection::Incoming).queue_depth;
+        assert_eq!(queue_depth(), 3);
+
+        drop(conditioners);
+        assert_eq!(queue_depth(), 0);
+    }
+}
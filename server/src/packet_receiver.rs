@@ -2,24 +2,44 @@ use std::net::SocketAddr;
 
 use crossbeam::channel::Receiver;
 
-use super::error::NaiaServerSocketError;
+use naia_socket_shared::ConnectionQuality;
+
+use super::{connections::Connections, error::NaiaServerSocketError};
 
 /// Used to receive packets from the Server Socket
 #[derive(Clone)]
 pub struct PacketReceiver {
     inner: Box<dyn PacketReceiverTrait>,
+    // Set if the Socket measures connection quality
+    quality: Option<Connections>,
 }
 
 impl PacketReceiver {
     /// Create a new PacketReceiver
     pub fn new(inner: Box<dyn PacketReceiverTrait>) -> Self {
-        PacketReceiver { inner }
+        PacketReceiver {
+            inner,
+            quality: None,
+        }
+    }
+
+    pub(crate) fn with_quality(mut self, quality: Option<Connections>) -> Self {
+        self.quality = quality;
+        self
     }
 
     /// Receives a packet from the Server Socket
     pub fn receive(&mut self) -> Result<Option<(SocketAddr, &[u8])>, NaiaServerSocketError> {
         return self.inner.receive();
     }
+
+    /// Gets the round trip time, jitter & packet loss of the connection to
+    /// the Client at the given address, as last reported by its pings.
+    /// Returns None if the Socket was created without a connection quality
+    /// config, or before the Client has measured anything
+    pub fn connection_quality(&self, address: &SocketAddr) -> Option<ConnectionQuality> {
+        self.quality.as_ref()?.get(address)?.quality
+    }
 }
 
 /// Used to receive packets from the Server Socket
@@ -0,0 +1,198 @@
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
+
+use crossbeam::channel::Sender;
+
+use naia_socket_shared::{Instant, RateLimitConfig, RealClock, SharedClock};
+
+use super::server_event::ServerEvent;
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Drops packets from remote addresses which send faster than allowed by a
+/// RateLimitConfig, using a token bucket per address
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: HashMap<SocketAddr, TokenBucket>,
+    event_sender: Sender<ServerEvent>,
+    last_prune: Instant,
+    clock: SharedClock,
+}
+
+impl RateLimiter {
+    /// Creates a new RateLimiter, reporting abusive addresses to the given
+    /// event channel
+    pub fn new(config: RateLimitConfig, event_sender: Sender<ServerEvent>) -> Self {
+        RateLimiter {
+            config,
+            buckets: HashMap::new(),
+            event_sender,
+            last_prune: Instant::now(),
+            clock: Arc::new(RealClock),
+        }
+    }
+
+    /// Reads the time from the given Clock, rather than the wall clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.last_prune = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Returns whether a packet of the given length from the given address is
+    /// within the rate limit. If not, the packet should be dropped
+    pub fn allow(&mut self, address: &SocketAddr, packet_len: usize) -> bool {
+        let now = self.clock.now();
+        if now.duration_since(&self.last_prune) >= PRUNE_INTERVAL {
+            self.prune(&now);
+        }
+
+        // Under a flood from many addresses, refuse new ones rather than let
+        // the buckets grow without limit until the next prune
+        if !self.buckets.contains_key(address) && self.buckets.len() >= self.config.max_addresses {
+            return false;
+        }
+
+        let config = &self.config;
+        let bucket = self
+            .buckets
+            .entry(*address)
+            .or_insert_with(|| TokenBucket::new(config, &now));
+        bucket.refill(config, &now);
+
+        let packet_len = packet_len as f64;
+        if bucket.packets >= 1.0 && bucket.bytes >= packet_len {
+            bucket.packets -= 1.0;
+            bucket.bytes -= packet_len;
+            return true;
+        }
+
+        if now.duration_since(&bucket.window_start) >= config.abuse_window {
+            bucket.window_start = now;
+            bucket.drops = 0;
+        }
+        bucket.drops += 1;
+        if bucket.drops == config.abuse_threshold {
+            log::warn!("{} exceeded the packet rate limit", address);
+            let _ = self
+                .event_sender
+                .try_send(ServerEvent::RateLimitExceeded(*address));
+        }
+
+        false
+    }
+
+    // Forget addresses whose bucket has refilled, they are no longer limited
+    fn prune(&mut self, now: &Instant) {
+        let config = &self.config;
+        self.buckets.retain(|_, bucket| {
+            bucket.refill(config, now);
+            bucket.packets < f64::from(config.packet_burst)
+                || bucket.bytes < f64::from(config.byte_burst)
+        });
+        self.last_prune = now.clone();
+    }
+}
+
+struct TokenBucket {
+    packets: f64,
+    bytes: f64,
+    last_refill: Instant,
+    drops: u32,
+    window_start: Instant,
+}
+
+impl TokenBucket {
+    fn new(config: &RateLimitConfig, now: &Instant) -> Self {
+        TokenBucket {
+            packets: f64::from(config.packet_burst),
+            bytes: f64::from(config.byte_burst),
+            last_refill: now.clone(),
+            drops: 0,
+            window_start: now.clone(),
+        }
+    }
+
+    fn refill(&mut self, config: &RateLimitConfig, now: &Instant) {
+        let elapsed = now.duration_since(&self.last_refill).as_secs_f64();
+        self.last_refill = now.clone();
+        self.packets = (self.packets + elapsed * f64::from(config.packets_per_second))
+            .min(f64::from(config.packet_burst));
+        self.bytes = (self.bytes + elapsed * f64::from(config.bytes_per_second))
+            .min(f64::from(config.byte_burst));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use crossbeam::channel;
+
+    use naia_socket_shared::{ManualClock, RateLimitConfig};
+
+    use super::RateLimiter;
+    use crate::server_event::ServerEvent;
+
+    #[test]
+    fn drops_packets_over_burst_and_reports_abuse() {
+        let (event_sender, event_receiver) = channel::unbounded();
+        let mut config = RateLimitConfig::new(10, 10_000);
+        config.abuse_threshold = 3;
+        let mut rate_limiter = RateLimiter::new(config, event_sender);
+        let address = "127.0.0.1:5000".parse().unwrap();
+
+        for _ in 0..10 {
+            assert!(rate_limiter.allow(&address, 100));
+        }
+        for _ in 0..5 {
+            assert!(!rate_limiter.allow(&address, 100));
+        }
+
+        assert_eq!(
+            event_receiver.try_recv(),
+            Ok(ServerEvent::RateLimitExceeded(address))
+        );
+        assert!(event_receiver.try_recv().is_err());
+
+        let other_address = "127.0.0.1:5001".parse().unwrap();
+        assert!(rate_limiter.allow(&other_address, 100));
+    }
+
+    #[test]
+    fn drops_packets_from_new_addresses_at_capacity() {
+        let (event_sender, _event_receiver) = channel::unbounded();
+        let mut config = RateLimitConfig::new(10, 10_000);
+        config.max_addresses = 2;
+        let mut rate_limiter = RateLimiter::new(config, event_sender);
+        let first = "127.0.0.1:5000".parse().unwrap();
+        let second = "127.0.0.1:5001".parse().unwrap();
+        let third = "127.0.0.1:5002".parse().unwrap();
+
+        assert!(rate_limiter.allow(&first, 100));
+        assert!(rate_limiter.allow(&second, 100));
+        assert!(!rate_limiter.allow(&third, 100));
+        assert_eq!(rate_limiter.buckets.len(), 2);
+
+        assert!(rate_limiter.allow(&first, 100));
+    }
+
+    #[test]
+    fn refills_as_the_clock_advances() {
+        let (event_sender, _event_receiver) = channel::unbounded();
+        let clock = Arc::new(ManualClock::new());
+        let mut rate_limiter = RateLimiter::new(RateLimitConfig::new(10, 10_000), event_sender)
+            .with_clock(clock.clone());
+        let address = "127.0.0.1:5000".parse().unwrap();
+
+        for _ in 0..10 {
+            assert!(rate_limiter.allow(&address, 100));
+        }
+        assert!(!rate_limiter.allow(&address, 100));
+
+        clock.advance(Duration::from_millis(500));
+        for _ in 0..5 {
+            assert!(rate_limiter.allow(&address, 100));
+        }
+        assert!(!rate_limiter.allow(&address, 100));
+    }
+}
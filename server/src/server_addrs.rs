@@ -3,7 +3,9 @@ use std::{default::Default, net::SocketAddr};
 /// List of addresses needed to start listening on a ServerSocket
 #[derive(Clone)]
 pub struct ServerAddrs {
-    /// IP Address to listen on for the signaling portion of WebRTC
+    /// IP Address to listen on for the signaling portion of WebRTC. Several
+    /// Sockets in the same process may share this address, as long as each
+    /// uses a different `rtc_endpoint_path`
     pub session_listen_addr: SocketAddr,
     /// IP Address to listen on for UDP WebRTC data channels
     pub webrtc_listen_addr: SocketAddr,
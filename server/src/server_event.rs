@@ -0,0 +1,19 @@
+use std::net::SocketAddr;
+
+use naia_socket_shared::DisconnectReason;
+
+/// An event raised by the Server Socket about a remote address
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ServerEvent {
+    /// The address has kept sending packets over the configured rate limit,
+    /// and may be worth banning
+    RateLimitExceeded(SocketAddr),
+    /// A Client connected from the address, when its WebRTC session was
+    /// established or its first packet arrived. Raised before any of its
+    /// packets can be received
+    Connected(SocketAddr),
+    /// The Client at the address disconnected. Clients only say goodbye if
+    /// the Socket was created with a keepalive config. Without one, only UDP
+    /// Clients time out, after a minute without a packet
+    Disconnected(SocketAddr, DisconnectReason),
+}
@@ -1,22 +1,53 @@
+use std::{net::SocketAddr, sync::Arc, thread, time::Duration};
+
 use crossbeam::channel;
 
 use futures_util::SinkExt;
+use smol::{block_on, Task};
+
+use log::info;
 
-use naia_socket_shared::SocketConfig;
+use naia_socket_shared::{
+    ControlPacket, IpFilter, LinkConditionerHandle, LinkDirection, LoopbackHub, RealClock,
+    SeededRandom, SharedClock, SocketConfig,
+};
 
-use crate::{executor, impls::Socket as AsyncSocket, io::Io};
+use crate::{executor, http_route::HttpRoute, impls::Socket as AsyncSocket, io::Io};
 
 use super::{
+    client_info::{ClientInfo, Transport},
     conditioned_packet_receiver::ConditionedPacketReceiverImpl,
+    connections::{Connections, DEFAULT_IDLE_TIMEOUT},
+    control_packet_receiver::ControlPacketReceiverImpl,
+    error::NaiaServerSocketError,
+    event_receiver::EventReceiver,
+    keepalive::Keepalive,
+    link_conditioner_profiles::{ClientConditioners, LinkConditionerProfiles},
     packet_receiver::{PacketReceiver, PacketReceiverImpl, PacketReceiverTrait},
     packet_sender::PacketSender,
+    rate_limiter::RateLimiter,
     server_addrs::ServerAddrs,
+    server_event::ServerEvent,
 };
 
+type Packet = (SocketAddr, Box<[u8]>);
+
+// How many events are kept waiting to be received before new ones are dropped
+const EVENT_CAPACITY: usize = 1024;
+// How often heartbeats & idle timeouts are checked for, so how late they may be
+const SWEEP_INTERVAL: Duration = Duration::from_millis(50);
+
 /// Socket is able to send and receive messages from remote Clients
 pub struct Socket {
     config: SocketConfig,
+    http_routes: Vec<HttpRoute>,
+    link_conditioner_profiles: Option<LinkConditionerProfiles>,
+    epoch: u64,
+    clock: SharedClock,
+    keepalive: Option<Keepalive>,
     io: Option<Io>,
+    listen_task: Option<Task<()>>,
+    sweep_task: Option<Task<()>>,
 }
 
 impl Socket {
@@ -24,12 +55,47 @@ impl Socket {
     pub fn new(config: &SocketConfig) -> Self {
         Socket {
             config: config.clone(),
+            http_routes: Vec::new(),
+            link_conditioner_profiles: config
+                .link_condition
+                .clone()
+                .map(LinkConditionerProfiles::new),
+            epoch: SeededRandom::from_entropy().next_u64(),
+            clock: Arc::new(RealClock),
+            keepalive: None,
             io: None,
+            listen_task: None,
+            sweep_task: None,
+        }
+    }
+
+    /// Times heartbeats, idle timeouts & rate limits with the given Clock,
+    /// rather than the wall clock, e.g. a ManualClock in tests. Must be called
+    /// before Socket.listen()
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        if self.io.is_some() {
+            panic!("Socket already listening! Set the clock before calling Socket.listen()");
+        }
+        self.clock = clock;
+        self
+    }
+
+    /// Adds an HttpRoute to be served by the signaling server alongside the
+    /// WebRTC session endpoint, e.g. a health check or the client bundle. Must
+    /// be called before Socket.listen()
+    pub fn add_route(&mut self, route: HttpRoute) {
+        if self.io.is_some() {
+            panic!("Socket already listening! Add routes before calling Socket.listen()");
         }
+        self.http_routes.push(route);
     }
 
-    /// Listens on the Socket for incoming communication from Clients
-    pub fn listen(&mut self, server_addrs: &ServerAddrs) {
+    /// Listens on the Socket for incoming communication from Clients. Returns
+    /// an error if the addresses can't be bound, or another Socket in this
+    /// process already uses the same RTC endpoint path on the same signaling
+    /// listener. The Socket stops listening, & frees its addresses & RTC
+    /// endpoint path, when it is dropped
+    pub fn listen(&mut self, server_addrs: &ServerAddrs) -> Result<(), NaiaServerSocketError> {
         if self.io.is_some() {
             panic!("Socket already listening!");
         }
@@ -37,55 +103,164 @@ impl Socket {
         // Set up receiver loop
         let (from_client_sender, from_client_receiver) = channel::unbounded();
         let (sender_sender, sender_receiver) = channel::bounded(1);
+        let (event_sender, event_receiver) = channel::bounded(EVENT_CAPACITY);
 
         let server_addrs_clone = server_addrs.clone();
         let config_clone = self.config.clone();
+        let http_routes = self.http_routes.clone();
+        let mut connections = Connections::new(AsyncSocket::TRANSPORT, event_sender.clone())
+            .with_clock(self.clock.clone());
+        if AsyncSocket::TRANSPORT == Transport::Udp && self.config.keepalive.is_none() {
+            // Otherwise nothing would forget UDP Clients, as they never close
+            connections = connections.with_idle_timeout(DEFAULT_IDLE_TIMEOUT);
+        }
+        let connections_clone = connections.clone();
+        let mut incoming_filter =
+            IncomingFilter::new(&self.config, event_sender, connections.clone());
 
-        executor::spawn(async move {
+        let listen_task = executor::spawn(async move {
             // Create async socket
-            let mut async_socket = AsyncSocket::listen(server_addrs_clone, config_clone).await;
+            let mut async_socket = match AsyncSocket::listen(
+                server_addrs_clone,
+                config_clone,
+                http_routes,
+                connections_clone,
+            )
+            .await
+            {
+                Ok(async_socket) => async_socket,
+                Err(err) => {
+                    let _ = sender_sender.send(Err(err));
+                    return;
+                }
+            };
 
-            sender_sender.send(async_socket.sender()).unwrap(); //TODO: handle result..
+            let _ = sender_sender.send(Ok(async_socket.sender()));
 
             loop {
                 let out_message = async_socket.receive().await;
-                from_client_sender.send(out_message).unwrap(); //TODO: handle
-                                                               // result..
+                if let Ok((address, payload)) = &out_message {
+                    if !incoming_filter.allow(address, payload) {
+                        continue;
+                    }
+                }
+                if from_client_sender.send(out_message).is_err() {
+                    // Every PacketReceiver has been dropped
+                    return;
+                }
             }
-        })
-        .detach();
+        });
+
+        let mut async_sender = sender_receiver
+            .recv()
+            .expect("Socket stopped before it started listening")?;
+        self.listen_task = Some(listen_task);
 
         // Set up sender loop
         let (to_client_sender, to_client_receiver) = channel::unbounded();
+        let mut outgoing_packets = OutgoingPackets::new(
+            to_client_receiver,
+            self.link_conditioner_profiles.clone(),
+            connections.clone(),
+        );
 
-        executor::spawn(async move {
-            // Create async socket
-            let mut async_sender = sender_receiver.recv().unwrap();
-
-            loop {
-                if let Ok(msg) = to_client_receiver.recv() {
-                    async_sender.send(msg).await.unwrap(); //TODO: handle
-                                                           // result..
+        // The sender loop blocks while it waits, so it runs on its own thread
+        // rather than occupying one of the executor's
+        thread::Builder::new()
+            .name("socket-sender".to_string())
+            .spawn(move || {
+                while let Some(packets) = outgoing_packets.next_ready() {
+                    for packet in packets {
+                        if block_on(async_sender.send(packet)).is_err() {
+                            // The Socket has stopped listening
+                            return;
+                        }
+                    }
                 }
-            }
-        })
-        .detach();
+            })
+            .expect("cannot spawn socket sender thread");
 
-        let conditioner_config = self.config.link_condition.clone();
+        self.start_io(
+            from_client_receiver,
+            to_client_sender,
+            event_receiver,
+            connections,
+        );
 
-        let receiver: Box<dyn PacketReceiverTrait> = match &conditioner_config {
-            Some(config) => Box::new(ConditionedPacketReceiverImpl::new(
-                from_client_receiver.clone(),
-                config,
-            )),
-            None => Box::new(PacketReceiverImpl::new(from_client_receiver.clone())),
-        };
-        let sender = PacketSender::new(to_client_sender.clone());
+        Ok(())
+    }
 
-        self.io = Some(Io {
-            packet_sender: sender,
-            packet_receiver: PacketReceiver::new(receiver),
+    /// Listens for Clients in the same process through the given
+    /// LoopbackHub, rather than over the network. The Socket's link
+    /// condition, IP filter & rate limit apply as they would to remote
+    /// Clients
+    pub fn listen_loopback(&mut self, hub: &LoopbackHub) {
+        if self.io.is_some() {
+            panic!("Socket already listening!");
+        }
+
+        // Packets from Clients are delivered as soon as they are sent
+        let (from_client_sender, from_client_receiver) = channel::unbounded();
+        let (event_sender, event_receiver) = channel::bounded(EVENT_CAPACITY);
+        let connections = Connections::new(Transport::Loopback, event_sender.clone())
+            .with_clock(self.clock.clone());
+        let mut incoming_filter =
+            IncomingFilter::new(&self.config, event_sender, connections.clone());
+        hub.attach_server(move |address, payload| {
+            if incoming_filter.allow(&address, &payload) {
+                let _ = from_client_sender.send(Ok((address, payload)));
+            }
         });
+
+        // Set up sender loop
+        let (to_client_sender, to_client_receiver) = channel::unbounded();
+        let mut outgoing_packets = OutgoingPackets::new(
+            to_client_receiver,
+            self.link_conditioner_profiles.clone(),
+            connections.clone(),
+        );
+        let hub_clone = hub.clone();
+
+        // The sender loop blocks while it waits, so it runs on its own thread
+        // rather than occupying one of the executor's
+        thread::Builder::new()
+            .name("loopback-sender".to_string())
+            .spawn(move || {
+                while let Some(packets) = outgoing_packets.next_ready() {
+                    for (address, payload) in packets {
+                        hub_clone.send_to_client(&address, payload);
+                    }
+                }
+            })
+            .expect("cannot spawn loopback sender thread");
+
+        info!("Loopback server listening as: {}", hub.server_addr());
+
+        self.start_io(
+            from_client_receiver,
+            to_client_sender,
+            event_receiver,
+            connections,
+        );
+    }
+
+    /// Gets a LinkConditionerHandle which can be used to change the default
+    /// simulated network conditions while the Socket runs, & to read what
+    /// they have done to the packets of every Client. Returns None if the
+    /// Socket was created without a link condition
+    pub fn link_conditioner(&self) -> Option<LinkConditionerHandle> {
+        self.link_conditioner_profiles
+            .as_ref()
+            .map(|profiles| profiles.handle())
+    }
+
+    /// Gets the LinkConditionerProfiles used to condition packets to & from
+    /// each Client, which can be changed while the Socket runs. Returns None
+    /// if the Socket was created without a link condition. To condition only
+    /// specific Clients, create the Socket with `LinkConditionerConfig::default()`,
+    /// which leaves everyone else unconditioned
+    pub fn link_conditioner_profiles(&self) -> Option<LinkConditionerProfiles> {
+        self.link_conditioner_profiles.clone()
     }
 
     /// Gets a PacketSender which can be used to send packets through the Socket
@@ -108,4 +283,618 @@ impl Socket {
             .packet_receiver
             .clone();
     }
+
+    /// Gets an EventReceiver which can be used to receive events from the
+    /// Socket, such as abusive addresses. Up to 1024 events are kept waiting
+    /// to be received, after which new ones are dropped
+    pub fn event_receiver(&self) -> EventReceiver {
+        return self
+            .io
+            .as_ref()
+            .expect("Socket is not listening yet! Call Socket.listen() before this.")
+            .event_receiver
+            .clone();
+    }
+
+    /// Gets the information of every connected Client
+    pub fn clients(&self) -> Vec<ClientInfo> {
+        self.connections().all()
+    }
+
+    /// Gets the information of the Client at the given address, if it is
+    /// connected
+    pub fn client(&self, address: &SocketAddr) -> Option<ClientInfo> {
+        self.connections().get(address)
+    }
+
+    /// Sets the identity the application has authenticated the Client at the
+    /// given address as, which is reported in its ClientInfo & selects its
+    /// identity's link conditioner profile, if it has one. Returns whether the
+    /// Client is connected
+    pub fn set_identity(&self, address: &SocketAddr, identity: impl Into<String>) -> bool {
+        let connected = self.connections().set_identity(address, identity.into());
+        if let (true, Some(profiles)) = (connected, &self.link_conditioner_profiles) {
+            profiles.identity_changed();
+        }
+        connected
+    }
+
+    /// Kicks the Client at the given address. It is told goodbye if the Socket
+    /// was created with a keepalive config, its WebRTC session is closed, &
+    /// any further packets from the address are refused. Raises a
+    /// Disconnected event if the Client was connected. The kick lasts until
+    /// `Socket.unkick()` is called for the address, or the Socket is dropped.
+    /// Packets are refused straight away, but WebRTC sessions are only checked
+    /// every 500ms, so the session may stay open until the next check
+    pub fn kick(&self, address: &SocketAddr) {
+        if self.keepalive.is_some() {
+            self.packet_sender()
+                .send(address, &ControlPacket::Goodbye.to_bytes());
+        }
+        self.connections().kick(address);
+    }
+
+    /// Lifts the kick of the given address, so a Client there can connect
+    /// again. Returns whether the address had been kicked
+    pub fn unkick(&self, address: &SocketAddr) -> bool {
+        self.connections().unkick(address)
+    }
+
+    /// Gets the Socket's epoch, a random number chosen when the Socket is
+    /// created. Clients with a keepalive config are told it in heartbeats, so
+    /// they can tell when the Server has restarted
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Says goodbye to every Client heard from, if the Socket was created
+    /// with a keepalive config, so they learn immediately that the Server is
+    /// going away. The goodbyes are sent in the background, so allow them a
+    /// moment before the process exits
+    pub fn shutdown(&mut self) {
+        if let (Some(keepalive), Some(io)) = (&self.keepalive, &self.io) {
+            keepalive.shutdown(&io.packet_sender);
+        }
+    }
+
+    fn connections(&self) -> &Connections {
+        &self
+            .io
+            .as_ref()
+            .expect("Socket is not listening yet! Call Socket.listen() before this.")
+            .connections
+    }
+
+    fn start_io(
+        &mut self,
+        from_client_receiver: channel::Receiver<Result<Packet, NaiaServerSocketError>>,
+        to_client_sender: channel::Sender<Packet>,
+        event_receiver: channel::Receiver<ServerEvent>,
+        connections: Connections,
+    ) {
+        if let Some(profiles) = &self.link_conditioner_profiles {
+            profiles.attach(&connections);
+        }
+        let receiver: Box<dyn PacketReceiverTrait> = match &self.link_conditioner_profiles {
+            Some(profiles) => Box::new(ConditionedPacketReceiverImpl::new(
+                from_client_receiver,
+                profiles,
+            )),
+            None => Box::new(PacketReceiverImpl::new(from_client_receiver)),
+        };
+        let packet_sender = PacketSender::new(to_client_sender);
+        let time_sync = self.config.time_sync.is_some();
+        let quality = self.config.connection_quality.is_some();
+        self.keepalive = self
+            .config
+            .keepalive
+            .clone()
+            .map(|config| Keepalive::new(config, self.epoch, &connections));
+        let receiver: Box<dyn PacketReceiverTrait> =
+            if time_sync || quality || self.keepalive.is_some() {
+                Box::new(ControlPacketReceiverImpl::new(
+                    receiver,
+                    packet_sender.clone(),
+                    connections.clone(),
+                    time_sync,
+                    quality,
+                    self.keepalive.clone(),
+                ))
+            } else {
+                receiver
+            };
+
+        // Heartbeats & idle timeouts don't wait for the application to receive
+        let keepalive = self.keepalive.clone();
+        let sweep_sender = packet_sender.clone();
+        let sweep_connections = connections.clone();
+        self.sweep_task = Some(executor::spawn(async move {
+            loop {
+                smol::Timer::after(SWEEP_INTERVAL).await;
+                sweep_connections.sweep_idle();
+                if let Some(keepalive) = &keepalive {
+                    keepalive.poll(&sweep_sender);
+                }
+            }
+        }));
+
+        self.io = Some(Io {
+            packet_sender,
+            packet_receiver: PacketReceiver::new(receiver)
+                .with_quality(quality.then(|| connections.clone())),
+            event_receiver: EventReceiver::new(event_receiver),
+            connections,
+        });
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        // Wait for the listening task to stop, so the Socket's addresses & RTC
+        // endpoint path are free to reuse once it has been dropped
+        if let Some(listen_task) = self.listen_task.take() {
+            block_on(listen_task.cancel());
+        }
+    }
+}
+
+// Drops packets from Clients which have been kicked, or aren't allowed by the
+// SocketConfig's IP filter or rate limit, & records that the rest arrived
+struct IncomingFilter {
+    ip_filter: IpFilter,
+    rate_limiter: Option<RateLimiter>,
+    connections: Connections,
+}
+
+impl IncomingFilter {
+    fn new(
+        config: &SocketConfig,
+        event_sender: channel::Sender<ServerEvent>,
+        connections: Connections,
+    ) -> Self {
+        IncomingFilter {
+            ip_filter: config.ip_filter.clone(),
+            rate_limiter: config.rate_limit.clone().map(|rate_limit_config| {
+                RateLimiter::new(rate_limit_config, event_sender)
+                    .with_clock(connections.clock().clone())
+            }),
+            connections,
+        }
+    }
+
+    fn allow(&mut self, address: &SocketAddr, payload: &[u8]) -> bool {
+        if self.connections.is_kicked(address) || !self.ip_filter.is_allowed(&address.ip()) {
+            return false;
+        }
+        if let Some(rate_limiter) = &mut self.rate_limiter {
+            if !rate_limiter.allow(address, payload.len()) {
+                return false;
+            }
+        }
+        self.connections.received(address, payload.len());
+        true
+    }
+}
+
+// Packets waiting to be sent to Clients, which are delayed by the link
+// conditioner if there is one
+struct OutgoingPackets {
+    to_client_receiver: channel::Receiver<Packet>,
+    link_conditioners: Option<ClientConditioners>,
+    connections: Connections,
+}
+
+impl OutgoingPackets {
+    fn new(
+        to_client_receiver: channel::Receiver<Packet>,
+        profiles: Option<LinkConditionerProfiles>,
+        connections: Connections,
+    ) -> Self {
+        OutgoingPackets {
+            to_client_receiver,
+            link_conditioners: profiles
+                .map(|profiles| ClientConditioners::new(profiles, LinkDirection::Outgoing)),
+            connections,
+        }
+    }
+
+    // Waits until there are packets ready to be sent, & counts them as sent.
+    // Returns None once every PacketSender has been dropped
+    fn next_ready(&mut self) -> Option<Vec<Packet>> {
+        let ready = self.next_unconditioned_or_ready()?;
+        for (address, payload) in &ready {
+            self.connections.sent(address, payload.len());
+        }
+        Some(ready)
+    }
+
+    fn next_unconditioned_or_ready(&mut self) -> Option<Vec<Packet>> {
+        let link_conditioners = match &mut self.link_conditioners {
+            Some(link_conditioners) => link_conditioners,
+            None => {
+                return self
+                    .to_client_receiver
+                    .recv()
+                    .ok()
+                    .map(|packet| vec![packet])
+            }
+        };
+
+        // Wait for the next packet, or until the next delayed packet is due to
+        // be sent
+        let next_packet = match link_conditioners.next_deadline() {
+            Some(deadline) => match self.to_client_receiver.recv_timeout(deadline.until()) {
+                Ok(packet) => Some(packet),
+                Err(channel::RecvTimeoutError::Timeout) => None,
+                Err(channel::RecvTimeoutError::Disconnected) => return None,
+            },
+            None => Some(self.to_client_receiver.recv().ok()?),
+        };
+        if let Some(packet) = next_packet {
+            link_conditioners.process_packet(packet);
+        }
+
+        let mut ready = Vec::new();
+        while let Some(packet) = link_conditioners.pop_item() {
+            ready.push(packet);
+        }
+        Some(ready)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use naia_socket_shared::{
+        ConnectionQuality, ConnectionQualityConfig, ControlPacket, DisconnectReason,
+        KeepaliveConfig, LinkConditionerConfig, LoopbackHub, ManualClock, SocketConfig,
+        TimeSyncConfig,
+    };
+
+    use super::Socket;
+    use crate::{ServerAddrs, ServerEvent, Transport};
+
+    #[test]
+    fn frees_addresses_when_dropped() {
+        // Over WebRTC, the data channel port is ephemeral, so the second
+        // Socket is refused for its RTC endpoint path
+        let server_addrs = ServerAddrs::new(
+            ([127, 0, 0, 1], free_session_port()).into(),
+            "127.0.0.1:0".parse().unwrap(),
+            "http://127.0.0.1:0",
+        );
+        let config = SocketConfig::default();
+
+        let mut first = Socket::new(&config);
+        first.listen(&server_addrs).unwrap();
+        assert!(Socket::new(&config).listen(&server_addrs).is_err());
+
+        drop(first);
+        let mut second = Socket::new(&config);
+        second.listen(&server_addrs).unwrap();
+    }
+
+    // Finds a port the OS considers free for the session listener, which is a
+    // TCP listener over WebRTC & the UDP socket itself otherwise
+    fn free_session_port() -> u16 {
+        #[cfg(feature = "use-webrtc")]
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        #[cfg(feature = "use-udp")]
+        let listener = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    #[test]
+    fn exchanges_packets_over_loopback() {
+        let config = SocketConfig {
+            link_condition: Some(LinkConditionerConfig::default().with_outgoing(50, 0, 0.0)),
+            ..Default::default()
+        };
+        let hub = LoopbackHub::new();
+        let mut server_socket = Socket::new(&config);
+        server_socket.listen_loopback(&hub);
+        let mut server_receiver = server_socket.packet_receiver();
+
+        let first = hub.add_client();
+        let second = hub.add_client();
+        first.send(&[1]);
+        second.send(&[2]);
+
+        let mut from_clients = Vec::new();
+        while let Ok(Some((address, payload))) = server_receiver.receive() {
+            from_clients.push((address, payload.to_vec()));
+        }
+        from_clients.sort();
+        assert_eq!(
+            from_clients,
+            vec![(first.address(), vec![1]), (second.address(), vec![2])]
+        );
+
+        let sent_at = Instant::now();
+        server_socket.packet_sender().send(&second.address(), &[3]);
+        let reply = loop {
+            if let Some(reply) = second.receive() {
+                break reply;
+            }
+            assert!(sent_at.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(&reply[..], &[3]);
+        assert!(sent_at.elapsed() >= Duration::from_millis(50));
+        assert_eq!(first.receive(), None);
+    }
+
+    #[test]
+    fn answers_control_packets() {
+        let config = SocketConfig {
+            time_sync: Some(TimeSyncConfig::default()),
+            connection_quality: Some(ConnectionQualityConfig::default()),
+            ..Default::default()
+        };
+        let hub = LoopbackHub::new();
+        let mut server_socket = Socket::new(&config);
+        server_socket.listen_loopback(&hub);
+        let mut server_receiver = server_socket.packet_receiver();
+
+        let client = hub.add_client();
+        let quality = ConnectionQuality {
+            rtt: Duration::from_millis(30),
+            outbound_loss: 0.5,
+            ..Default::default()
+        };
+        client.send(&ControlPacket::TimeProbe { id: 3 }.to_bytes());
+        client.send(
+            &ControlPacket::Ping {
+                id: 4,
+                report: Some(quality),
+            }
+            .to_bytes(),
+        );
+        client.send(&[1]);
+
+        // The control packets are answered & only the application packet is
+        // returned
+        assert_eq!(
+            server_receiver.receive().unwrap(),
+            Some((client.address(), &[1][..]))
+        );
+        assert_eq!(
+            server_receiver.connection_quality(&client.address()),
+            Some(quality.as_seen_by_peer())
+        );
+
+        let sent_at = Instant::now();
+        let mut replies = Vec::new();
+        while replies.len() < 2 {
+            match client.receive() {
+                Some(reply) => replies.push(ControlPacket::from_bytes(&reply).unwrap()),
+                None => {
+                    assert!(sent_at.elapsed() < Duration::from_secs(5));
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+        }
+        match &replies[0] {
+            ControlPacket::TimeReply { id, received, sent } => {
+                assert_eq!(*id, 3);
+                assert!(received <= sent);
+            }
+            other => panic!("expected a TimeReply, got {:?}", other),
+        }
+        assert_eq!(
+            replies[1],
+            ControlPacket::Pong {
+                id: 4,
+                pings_received: 1
+            }
+        );
+
+        // The Client's quality is forgotten once it disconnects
+        assert_eq!(
+            server_socket.client(&client.address()).unwrap().quality,
+            Some(quality.as_seen_by_peer())
+        );
+        server_socket.kick(&client.address());
+        assert_eq!(server_receiver.connection_quality(&client.address()), None);
+    }
+
+    #[test]
+    fn tracks_connects_and_disconnects() {
+        let config = SocketConfig {
+            keepalive: Some(KeepaliveConfig::new(
+                Duration::from_millis(10),
+                Duration::from_millis(100),
+            )),
+            ..Default::default()
+        };
+        let hub = LoopbackHub::new();
+        let mut server_socket = Socket::new(&config);
+        server_socket.listen_loopback(&hub);
+        let mut server_receiver = server_socket.packet_receiver();
+        let mut event_receiver = server_socket.event_receiver();
+
+        let leaving = hub.add_client();
+        let quiet = hub.add_client();
+        leaving.send(&[1]);
+        leaving.send(&ControlPacket::Goodbye.to_bytes());
+        quiet.send(&[2]);
+
+        let started = Instant::now();
+        let mut events = Vec::new();
+        let mut heartbeat = None;
+        while events.len() < 4 {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            while let Ok(Some(_)) = server_receiver.receive() {}
+            while let Some(event) = event_receiver.receive() {
+                events.push(event);
+            }
+            if let Some(packet) = quiet.receive() {
+                heartbeat = ControlPacket::from_bytes(&packet);
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(
+            events,
+            vec![
+                ServerEvent::Connected(leaving.address()),
+                ServerEvent::Connected(quiet.address()),
+                ServerEvent::Disconnected(leaving.address(), DisconnectReason::Goodbye),
+                ServerEvent::Disconnected(quiet.address(), DisconnectReason::TimedOut),
+            ]
+        );
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert_eq!(
+            heartbeat,
+            Some(ControlPacket::Heartbeat {
+                epoch: Some(server_socket.epoch())
+            })
+        );
+    }
+
+    #[test]
+    fn times_out_clients_without_receiving() {
+        let config = SocketConfig {
+            keepalive: Some(KeepaliveConfig::new(
+                Duration::from_secs(1),
+                Duration::from_secs(5),
+            )),
+            ..Default::default()
+        };
+        let clock = Arc::new(ManualClock::new());
+        let hub = LoopbackHub::new();
+        let mut server_socket = Socket::new(&config).with_clock(clock.clone());
+        server_socket.listen_loopback(&hub);
+        let mut event_receiver = server_socket.event_receiver();
+
+        // The application never receives, yet the Client is sent heartbeats &
+        // disconnected once it goes quiet
+        let client = hub.add_client();
+        client.send(&[1]);
+        clock.advance(Duration::from_millis(1100));
+        let started = Instant::now();
+        let mut heartbeat = None;
+        while heartbeat.is_none() {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            heartbeat = client.receive();
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(
+            ControlPacket::from_bytes(&heartbeat.unwrap()),
+            Some(ControlPacket::Heartbeat {
+                epoch: Some(server_socket.epoch())
+            })
+        );
+
+        clock.advance(Duration::from_secs(5));
+        let mut events = Vec::new();
+        while events.len() < 2 {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            while let Some(event) = event_receiver.receive() {
+                events.push(event);
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(
+            events,
+            vec![
+                ServerEvent::Connected(client.address()),
+                ServerEvent::Disconnected(client.address(), DisconnectReason::TimedOut),
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_and_kicks_clients() {
+        let config = SocketConfig {
+            keepalive: Some(KeepaliveConfig::new(
+                Duration::from_secs(10),
+                Duration::from_secs(60),
+            )),
+            ..Default::default()
+        };
+        let hub = LoopbackHub::new();
+        let mut server_socket = Socket::new(&config);
+        server_socket.listen_loopback(&hub);
+        let mut server_receiver = server_socket.packet_receiver();
+        let server_sender = server_socket.packet_sender();
+        let mut event_receiver = server_socket.event_receiver();
+
+        let client = hub.add_client();
+        let address = client.address();
+        client.send(&[1, 2, 3]);
+
+        let started = Instant::now();
+        let mut received = None;
+        while received.is_none() {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            received = server_receiver.receive().unwrap();
+            thread::sleep(Duration::from_millis(1));
+        }
+        server_sender.send(&address, &[4, 5]);
+        while client.receive().is_none() {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert!(server_socket.set_identity(&address, "player"));
+        let clients = server_socket.clients();
+        assert_eq!(clients.len(), 1);
+        let info = server_socket.client(&address).unwrap();
+        assert_eq!(info.transport, Transport::Loopback);
+        assert_eq!(info.identity.as_deref(), Some("player"));
+        assert_eq!((info.packets_in, info.bytes_in), (1, 3));
+        assert_eq!((info.packets_out, info.bytes_out), (1, 2));
+
+        server_socket.kick(&address);
+        assert!(server_socket.clients().is_empty());
+        assert_eq!(
+            event_receiver.receive(),
+            Some(ServerEvent::Connected(address))
+        );
+        assert_eq!(
+            event_receiver.receive(),
+            Some(ServerEvent::Disconnected(address, DisconnectReason::Kicked))
+        );
+        let mut goodbye = None;
+        while goodbye.is_none() {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            goodbye = client.receive();
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(
+            ControlPacket::from_bytes(&goodbye.unwrap()),
+            Some(ControlPacket::Goodbye)
+        );
+
+        // Packets from a kicked Client are refused, & don't reconnect it
+        client.send(&[6]);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(server_receiver.receive().unwrap(), None);
+        assert!(server_socket.client(&address).is_none());
+        assert_eq!(event_receiver.receive(), None);
+        assert!(!server_socket.set_identity(&address, "player"));
+
+        // Once unkicked, the Client can connect again
+        assert!(server_socket.unkick(&address));
+        assert!(!server_socket.unkick(&address));
+        client.send(&[7]);
+        loop {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            if let Some((from, payload)) = server_receiver.receive().unwrap() {
+                assert_eq!((from, payload), (address, &[7][..]));
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(
+            event_receiver.receive(),
+            Some(ServerEvent::Connected(address))
+        );
+    }
 }
@@ -0,0 +1,92 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use super::Instant;
+
+/// A source of the current time. Timers, TimeQueues, LinkConditioners &
+/// TimeSync read the time from a Clock, as does a server Socket given one
+/// with `with_clock()` for its heartbeats, idle timeouts & rate limits, so
+/// that tests can replace the wall clock with a ManualClock. The delivery
+/// threads of a server's link conditioner profiles & the checks of WebRTC
+/// sessions still read the wall clock
+pub trait Clock: Send + Sync {
+    /// Gets the current time
+    fn now(&self) -> Instant;
+
+    /// Returns time elapsed since the given Instant, or zero if it is in the
+    /// future
+    fn elapsed(&self, instant: &Instant) -> Duration {
+        self.now().duration_since(instant)
+    }
+
+    /// Returns time until the given Instant occurs, or zero if it has passed
+    fn until(&self, instant: &Instant) -> Duration {
+        instant.duration_since(&self.now())
+    }
+}
+
+/// A Clock which reads the platform's wall clock
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A Clock which only moves when it is advanced, so that simulated time can
+/// pass without waiting for it. Clones share the same time
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed_micros: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Creates a new ManualClock, stopped at the current wall clock time
+    pub fn new() -> Self {
+        ManualClock {
+            start: Instant::now(),
+            elapsed_micros: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Moves the clock forward by the given Duration
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::AcqRel);
+    }
+
+    /// Returns how far the clock has been advanced since it was created
+    pub fn total_elapsed(&self) -> Duration {
+        Duration::from_micros(self.elapsed_micros.load(Ordering::Acquire))
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        ManualClock::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        let mut now = self.start.clone();
+        now.add_micros(self.elapsed_micros.load(Ordering::Acquire));
+        now
+    }
+}
+
+/// A Clock which can be shared between, & stored in, Timers, TimeQueues &
+/// LinkConditioners
+pub type SharedClock = Arc<dyn Clock>;
+
+pub(crate) fn real_clock() -> SharedClock {
+    Arc::new(RealClock)
+}
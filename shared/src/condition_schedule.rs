@@ -0,0 +1,346 @@
+use std::{error::Error, fmt, fs, path::Path, sync::Arc, time::Duration};
+
+/// A script of changing network conditions, applied by a LinkConditioner on
+/// top of its LinkConditionerConfig. A schedule either changes the latency,
+/// jitter & loss over time, or replays a trace of per-packet delays & losses.
+/// Time is measured from when the LinkConditioner is created
+#[derive(Clone, Debug)]
+pub struct ConditionSchedule {
+    kind: Arc<ScheduleKind>,
+    repeat: bool,
+}
+
+#[derive(Clone, Debug)]
+enum ScheduleKind {
+    Keyframes {
+        keyframes: Vec<Keyframe>,
+        outages: Vec<(Duration, Duration)>,
+    },
+    Trace(Vec<Option<u32>>),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Keyframe {
+    at: Duration,
+    conditions: ScheduledConditions,
+    ramp: bool,
+}
+
+/// The latency, jitter & loss applied at a point in a ConditionSchedule
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScheduledConditions {
+    /// Latency in milliseconds
+    pub latency: u32,
+    /// Jitter in milliseconds
+    pub jitter: u32,
+    /// The % chance that a packet will be dropped, between 0 and 1
+    pub loss: f32,
+}
+
+impl ConditionSchedule {
+    /// Creates a new, empty ConditionSchedule, which leaves the config's own
+    /// conditions in place until its first change
+    pub fn new() -> Self {
+        ConditionSchedule::from_kind(ScheduleKind::Keyframes {
+            keyframes: Vec::new(),
+            outages: Vec::new(),
+        })
+    }
+
+    /// Creates a ConditionSchedule which replays a trace of per-packet
+    /// delays in milliseconds, one per packet in order. `None` entries are
+    /// lost packets
+    pub fn from_trace(delays: Vec<Option<u32>>) -> Self {
+        ConditionSchedule::from_kind(ScheduleKind::Trace(delays))
+    }
+
+    /// Switches to the given conditions at the given time
+    pub fn set_at(self, at: Duration, latency: u32, jitter: u32, loss: f32) -> Self {
+        self.add_keyframe(at, latency, jitter, loss, false)
+    }
+
+    /// Changes gradually from the previous conditions to the given conditions,
+    /// reaching them at the given time
+    pub fn ramp_to(self, at: Duration, latency: u32, jitter: u32, loss: f32) -> Self {
+        self.add_keyframe(at, latency, jitter, loss, true)
+    }
+
+    /// Drops every packet for the given duration, starting at the given time
+    pub fn outage(mut self, at: Duration, duration: Duration) -> Self {
+        if let ScheduleKind::Keyframes { outages, .. } = self.kind_mut() {
+            outages.push((at, at + duration));
+        }
+        self
+    }
+
+    /// Starts the schedule over once it reaches its end, rather than keeping
+    /// the final conditions
+    pub fn repeat(mut self) -> Self {
+        self.repeat = true;
+        self
+    }
+
+    /// Parses a schedule from CSV, with one change per line, in the form
+    /// `time_ms,latency_ms,jitter_ms,loss` for an immediate change,
+    /// `time_ms,latency_ms,jitter_ms,loss,ramp` for a gradual change, or
+    /// `time_ms,outage,duration_ms` for an outage. Empty lines & lines
+    /// starting with '#' are ignored
+    pub fn from_csv(csv: &str) -> Result<Self, ScheduleParseError> {
+        let mut schedule = ConditionSchedule::new();
+        for (line_number, fields) in csv_lines(csv) {
+            let error = |reason: &str| ScheduleParseError::new(line_number, reason);
+            let at = Duration::from_millis(parse_field(&fields, 0, line_number)?);
+            if fields.get(1) == Some(&"outage") {
+                let duration = Duration::from_millis(parse_field(&fields, 2, line_number)?);
+                schedule = schedule.outage(at, duration);
+                continue;
+            }
+            let latency = parse_field(&fields, 1, line_number)?;
+            let jitter = parse_field(&fields, 2, line_number)?;
+            let loss = parse_field(&fields, 3, line_number)?;
+            schedule = match fields.get(4) {
+                None => schedule.set_at(at, latency, jitter, loss),
+                Some(&"ramp") => schedule.ramp_to(at, latency, jitter, loss),
+                Some(_) => return Err(error("expected 'ramp' as the fifth field")),
+            };
+        }
+        Ok(schedule)
+    }
+
+    /// Parses a trace of per-packet delays from CSV, with one packet per
+    /// line. The last field of each line is the packet's delay in
+    /// milliseconds, or 'lost', so captures with a leading timestamp or
+    /// sequence number column can be used as they are
+    pub fn trace_from_csv(csv: &str) -> Result<Self, ScheduleParseError> {
+        let mut delays = Vec::new();
+        for (line_number, fields) in csv_lines(csv) {
+            let last = fields.len() - 1;
+            if fields[last] == "lost" {
+                delays.push(None);
+            } else {
+                delays.push(Some(parse_field(&fields, last, line_number)?));
+            }
+        }
+        Ok(ConditionSchedule::from_trace(delays))
+    }
+
+    /// Reads a schedule from a CSV file, in the format of `from_csv()`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScheduleParseError> {
+        ConditionSchedule::from_csv(&read_file(path.as_ref())?)
+    }
+
+    /// Reads a per-packet trace from a CSV file, in the format of
+    /// `trace_from_csv()`
+    pub fn load_trace(path: impl AsRef<Path>) -> Result<Self, ScheduleParseError> {
+        ConditionSchedule::trace_from_csv(&read_file(path.as_ref())?)
+    }
+
+    /// Gets the conditions which apply at the given time since the schedule
+    /// started, given the config's own conditions. Returns None for trace
+    /// schedules, which apply per packet instead
+    pub fn conditions_at(
+        &self,
+        elapsed: Duration,
+        base: ScheduledConditions,
+    ) -> Option<ScheduledConditions> {
+        let (keyframes, outages) = match self.kind.as_ref() {
+            ScheduleKind::Keyframes { keyframes, outages } => (keyframes, outages),
+            ScheduleKind::Trace(_) => return None,
+        };
+
+        let end = keyframes
+            .iter()
+            .map(|keyframe| keyframe.at)
+            .chain(outages.iter().map(|(_, outage_end)| *outage_end))
+            .max()
+            .unwrap_or(Duration::ZERO);
+        let elapsed = if self.repeat && end > Duration::ZERO {
+            Duration::from_nanos((elapsed.as_nanos() % end.as_nanos()) as u64)
+        } else {
+            elapsed
+        };
+
+        let mut conditions = base;
+        let mut previous = (Duration::ZERO, base);
+        for keyframe in keyframes {
+            if keyframe.at <= elapsed {
+                conditions = keyframe.conditions;
+                previous = (keyframe.at, keyframe.conditions);
+                continue;
+            }
+            if keyframe.ramp {
+                let (previous_at, previous_conditions) = previous;
+                let fraction = (elapsed - previous_at).as_secs_f32()
+                    / (keyframe.at - previous_at).as_secs_f32();
+                conditions = interpolate(&previous_conditions, &keyframe.conditions, fraction);
+            }
+            break;
+        }
+
+        if outages
+            .iter()
+            .any(|(start, outage_end)| *start <= elapsed && elapsed < *outage_end)
+        {
+            conditions.loss = 1.0;
+        }
+        Some(conditions)
+    }
+
+    /// Gets the delay in milliseconds of the packet at the given position in
+    /// a trace schedule, which is None if the packet is lost. Returns None for
+    /// keyframe schedules, & for packets beyond the end of a trace which
+    /// doesn't repeat
+    pub fn trace_delay(&self, packet_index: usize) -> Option<Option<u32>> {
+        match self.kind.as_ref() {
+            ScheduleKind::Trace(delays) if !delays.is_empty() => {
+                let index = if self.repeat {
+                    packet_index % delays.len()
+                } else {
+                    packet_index
+                };
+                delays.get(index).copied()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns whether both ConditionSchedules are the same schedule
+    pub fn same_as(&self, other: &ConditionSchedule) -> bool {
+        Arc::ptr_eq(&self.kind, &other.kind) && self.repeat == other.repeat
+    }
+
+    fn from_kind(kind: ScheduleKind) -> Self {
+        ConditionSchedule {
+            kind: Arc::new(kind),
+            repeat: false,
+        }
+    }
+
+    fn add_keyframe(
+        mut self,
+        at: Duration,
+        latency: u32,
+        jitter: u32,
+        loss: f32,
+        ramp: bool,
+    ) -> Self {
+        if let ScheduleKind::Keyframes { keyframes, .. } = self.kind_mut() {
+            let index = keyframes.partition_point(|keyframe| keyframe.at <= at);
+            keyframes.insert(
+                index,
+                Keyframe {
+                    at,
+                    conditions: ScheduledConditions {
+                        latency,
+                        jitter,
+                        loss,
+                    },
+                    ramp,
+                },
+            );
+        }
+        self
+    }
+
+    fn kind_mut(&mut self) -> &mut ScheduleKind {
+        Arc::make_mut(&mut self.kind)
+    }
+}
+
+impl Default for ConditionSchedule {
+    fn default() -> Self {
+        ConditionSchedule::new()
+    }
+}
+
+/// An error returned when a ConditionSchedule can't be read or parsed
+#[derive(Debug)]
+pub struct ScheduleParseError(String);
+
+impl ScheduleParseError {
+    fn new(line_number: usize, reason: &str) -> Self {
+        ScheduleParseError(format!("line {}: {}", line_number, reason))
+    }
+}
+
+impl fmt::Display for ScheduleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for ScheduleParseError {}
+
+fn interpolate(
+    from: &ScheduledConditions,
+    to: &ScheduledConditions,
+    fraction: f32,
+) -> ScheduledConditions {
+    let lerp = |from: f32, to: f32| from + (to - from) * fraction;
+    ScheduledConditions {
+        latency: lerp(from.latency as f32, to.latency as f32).round() as u32,
+        jitter: lerp(from.jitter as f32, to.jitter as f32).round() as u32,
+        loss: lerp(from.loss, to.loss),
+    }
+}
+
+fn csv_lines(csv: &str) -> impl Iterator<Item = (usize, Vec<&str>)> {
+    csv.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_number, line)| (line_number, line.split(',').map(str::trim).collect()))
+}
+
+fn parse_field<T: std::str::FromStr>(
+    fields: &[&str],
+    index: usize,
+    line_number: usize,
+) -> Result<T, ScheduleParseError> {
+    let field = fields
+        .get(index)
+        .ok_or_else(|| ScheduleParseError::new(line_number, "missing field"))?;
+    field.parse().map_err(|_| {
+        ScheduleParseError::new(line_number, &format!("'{}' is not a valid value", field))
+    })
+}
+
+fn read_file(path: &Path) -> Result<String, ScheduleParseError> {
+    fs::read_to_string(path)
+        .map_err(|err| ScheduleParseError(format!("can't read {}: {}", path.display(), err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{ConditionSchedule, ScheduledConditions};
+
+    #[test]
+    fn parses_ramps_and_outages() {
+        let schedule = ConditionSchedule::from_csv(
+            "# time_ms,latency,jitter,loss\n\
+             1000,100,10,0.0\n\
+             2000,200,20,0.5,ramp\n\
+             3000,outage,500\n",
+        )
+        .unwrap();
+        let base = ScheduledConditions {
+            latency: 0,
+            jitter: 0,
+            loss: 0.0,
+        };
+        let at = |millis| {
+            schedule
+                .conditions_at(Duration::from_millis(millis), base)
+                .unwrap()
+        };
+
+        assert_eq!(at(500), base);
+        assert_eq!(at(1500).latency, 150);
+        assert_eq!(at(2500).latency, 200);
+        assert_eq!(at(3200).loss, 1.0);
+        assert_eq!(at(4000).loss, 0.5);
+        assert!(ConditionSchedule::from_csv("1000,100,10").is_err());
+    }
+}
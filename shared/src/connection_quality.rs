@@ -0,0 +1,271 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::{
+    connection_quality_config::ConnectionQualityConfig, control_packet::ControlPacket,
+    timer::Timer, Instant,
+};
+
+/// The quality of a connection, as measured by pings
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ConnectionQuality {
+    /// The smoothed round trip time
+    pub rtt: Duration,
+    /// The smoothed mean deviation of the round trip time
+    pub jitter: Duration,
+    /// The fraction of packets sent which are lost before they arrive,
+    /// between 0 & 1
+    pub outbound_loss: f32,
+    /// The fraction of packets sent by the other side which are lost before
+    /// they arrive, between 0 & 1
+    pub inbound_loss: f32,
+    /// The round trip time measured by the underlying transport, e.g. the
+    /// WebRTC ICE candidate pair, where it is available
+    pub transport_rtt: Option<Duration>,
+}
+
+impl ConnectionQuality {
+    /// Returns the same quality as seen from the other side of the
+    /// connection, where outbound loss is inbound & vice versa. The other side
+    /// has no transport round trip time
+    pub fn as_seen_by_peer(&self) -> ConnectionQuality {
+        ConnectionQuality {
+            rtt: self.rtt,
+            jitter: self.jitter,
+            outbound_loss: self.inbound_loss,
+            inbound_loss: self.outbound_loss,
+            transport_rtt: None,
+        }
+    }
+}
+
+/// Measures the quality of the connection to the Server on the Client, from
+/// the replies to pings. Clones share the same measurements
+#[derive(Clone)]
+pub struct QualityEstimator {
+    config: ConnectionQualityConfig,
+    state: Arc<Mutex<QualityState>>,
+}
+
+struct QualityState {
+    timer: Timer,
+    next_id: u32,
+    pending: VecDeque<(u32, Instant)>,
+    rtt: Option<Duration>,
+    jitter: Duration,
+    // The id of each recent pong, with the number of pings the Server had
+    // received when it replied
+    loss_samples: VecDeque<(u32, u32)>,
+    outbound_loss: f32,
+    inbound_loss: f32,
+    transport_rtt: Option<Duration>,
+}
+
+impl QualityEstimator {
+    /// Creates a new QualityEstimator, with no measurements until the first
+    /// pong
+    pub fn new(config: ConnectionQualityConfig) -> Self {
+        let mut timer = Timer::new(config.ping_interval);
+        timer.ring_manual();
+        QualityEstimator {
+            config,
+            state: Arc::new(Mutex::new(QualityState {
+                timer,
+                next_id: 0,
+                pending: VecDeque::new(),
+                rtt: None,
+                jitter: Duration::ZERO,
+                loss_samples: VecDeque::new(),
+                outbound_loss: 0.0,
+                inbound_loss: 0.0,
+                transport_rtt: None,
+            })),
+        }
+    }
+
+    /// Gets the config the QualityEstimator was created with
+    pub fn config(&self) -> &ConnectionQualityConfig {
+        &self.config
+    }
+
+    /// Returns a ping to send to the Server if one is due, once every ping
+    /// interval
+    pub fn next_ping(&self) -> Option<ControlPacket> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if !state.timer.ringing() {
+                return None;
+            }
+            state.timer.reset();
+        }
+        Some(self.record_ping(Instant::now()))
+    }
+
+    /// Records a ping sent at the given Instant, & returns it. The ping
+    /// carries the current measurements, so the Server knows them too
+    pub fn record_ping(&self, sent_at: Instant) -> ControlPacket {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id = state.next_id.wrapping_add(1);
+        state.pending.push_back((id, sent_at));
+        while state.pending.len() > self.loss_window() {
+            state.pending.pop_front();
+        }
+        ControlPacket::Ping {
+            id,
+            report: state.quality(),
+        }
+    }
+
+    /// Records the pong replying to the ping with the given id, which
+    /// arrived at `received_at`, given the number of pings the Server had
+    /// received when it replied
+    pub fn record_pong(&self, id: u32, pings_received: u32, received_at: &Instant) {
+        let mut state = self.state.lock().unwrap();
+        let index = match state.pending.iter().position(|(ping_id, _)| *ping_id == id) {
+            Some(index) => index,
+            None => return,
+        };
+        let (_, sent_at) = state.pending.remove(index).unwrap();
+        state.update_rtt(received_at.duration_since(&sent_at));
+
+        // Pongs which arrive out of order say nothing new about loss
+        if let Some((last_id, _)) = state.loss_samples.back() {
+            if (id.wrapping_sub(*last_id) as i32) <= 0 {
+                return;
+            }
+        }
+        state.loss_samples.push_back((id, pings_received));
+        while state.loss_samples.len() > self.loss_window() {
+            state.loss_samples.pop_front();
+        }
+        state.update_loss();
+    }
+
+    /// Sets the round trip time measured by the underlying transport, or None
+    /// if it isn't available
+    pub fn set_transport_rtt(&self, rtt: Option<Duration>) {
+        self.state.lock().unwrap().transport_rtt = rtt;
+    }
+
+    /// Returns the current measurements, or None if no ping has been answered
+    /// yet
+    pub fn quality(&self) -> Option<ConnectionQuality> {
+        self.state.lock().unwrap().quality()
+    }
+
+    fn loss_window(&self) -> usize {
+        self.config.loss_window.max(2)
+    }
+}
+
+impl QualityState {
+    // Smoothed as TCP does, see RFC 6298
+    fn update_rtt(&mut self, sample: Duration) {
+        match self.rtt {
+            None => {
+                self.rtt = Some(sample);
+                self.jitter = sample / 2;
+            }
+            Some(rtt) => {
+                self.jitter = self.jitter * 3 / 4 + sample.abs_diff(rtt) / 4;
+                self.rtt = Some(rtt * 7 / 8 + sample / 8);
+            }
+        }
+    }
+
+    // Every ping the Server receives is answered, so comparing the pings sent,
+    // the pings the Server received, & the pongs received between the oldest
+    // & newest samples gives the loss each way
+    fn update_loss(&mut self) {
+        let (first_id, first_received) = match self.loss_samples.front() {
+            Some(sample) => *sample,
+            None => return,
+        };
+        let (last_id, last_received) = *self.loss_samples.back().unwrap();
+        let sent = last_id.wrapping_sub(first_id);
+        let arrived = last_received.wrapping_sub(first_received);
+        let returned = (self.loss_samples.len() - 1) as u32;
+
+        self.outbound_loss = loss_fraction(arrived, sent);
+        self.inbound_loss = loss_fraction(returned, arrived);
+    }
+
+    fn quality(&self) -> Option<ConnectionQuality> {
+        Some(ConnectionQuality {
+            rtt: self.rtt?,
+            jitter: self.jitter,
+            outbound_loss: self.outbound_loss,
+            inbound_loss: self.inbound_loss,
+            transport_rtt: self.transport_rtt,
+        })
+    }
+}
+
+fn loss_fraction(delivered: u32, sent: u32) -> f32 {
+    if sent == 0 {
+        return 0.0;
+    }
+    1.0 - delivered.min(sent) as f32 / sent as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::QualityEstimator;
+    use crate::{ConnectionQualityConfig, ControlPacket, Instant};
+
+    #[test]
+    fn measures_rtt_jitter_and_loss_each_way() {
+        let estimator = QualityEstimator::new(ConnectionQualityConfig::default());
+        let start = Instant::now();
+        let at = |millis: u64| start.clone() + Duration::from_millis(millis);
+        assert_eq!(estimator.quality(), None);
+
+        // 10 pings, of which the Server receives all but #3 & #7, & whose
+        // pongs all arrive but #5's. Round trips alternate 40ms & 60ms
+        let mut pings_received = 0;
+        for id in 0..10u32 {
+            let sent_at = at(u64::from(id) * 100);
+            match estimator.record_ping(sent_at.clone()) {
+                ControlPacket::Ping { id: ping_id, .. } => assert_eq!(ping_id, id),
+                other => panic!("expected a Ping, got {:?}", other),
+            }
+            if id == 3 || id == 7 {
+                continue;
+            }
+            pings_received += 1;
+            if id == 5 {
+                continue;
+            }
+            let rtt = if id % 2 == 0 { 40 } else { 60 };
+            estimator.record_pong(id, pings_received, &(sent_at + Duration::from_millis(rtt)));
+        }
+
+        let quality = estimator.quality().unwrap();
+        assert!(quality.rtt > Duration::from_millis(40));
+        assert!(quality.rtt < Duration::from_millis(60));
+        assert!(quality.jitter > Duration::ZERO);
+        // Between pongs #0 & #9, 9 pings were sent & 7 arrived, & 6 of those
+        // 7 pongs arrived
+        assert!((quality.outbound_loss - 2.0 / 9.0).abs() < 0.001);
+        assert!((quality.inbound_loss - 1.0 / 7.0).abs() < 0.001);
+
+        let peer_quality = quality.as_seen_by_peer();
+        assert_eq!(peer_quality.inbound_loss, quality.outbound_loss);
+
+        let ping = estimator.record_ping(at(1000));
+        match ControlPacket::from_bytes(&ping.to_bytes()) {
+            Some(ControlPacket::Ping { report, .. }) => {
+                let report = report.unwrap();
+                assert_eq!(report.rtt.as_micros(), quality.rtt.as_micros());
+                assert!((report.outbound_loss - quality.outbound_loss).abs() < 0.001);
+            }
+            other => panic!("expected a Ping, got {:?}", other),
+        }
+    }
+}
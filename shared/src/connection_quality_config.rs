@@ -0,0 +1,27 @@
+use std::{default::Default, time::Duration};
+
+/// Contains configuration for measuring the quality of the connection between
+/// Client & Server. Must be set on both the Client & the Server Socket
+#[derive(Clone)]
+pub struct ConnectionQualityConfig {
+    /// How often the Client sends a ping to the Server
+    pub ping_interval: Duration,
+    /// Number of recent pings packet loss is measured over
+    pub loss_window: usize,
+}
+
+impl ConnectionQualityConfig {
+    /// Creates a new ConnectionQualityConfig, pinging at the given interval
+    pub fn new(ping_interval: Duration) -> Self {
+        ConnectionQualityConfig {
+            ping_interval,
+            loss_window: 32,
+        }
+    }
+}
+
+impl Default for ConnectionQualityConfig {
+    fn default() -> Self {
+        ConnectionQualityConfig::new(Duration::from_millis(250))
+    }
+}
@@ -0,0 +1,194 @@
+use std::time::Duration;
+
+use super::{connection_quality::ConnectionQuality, timestamp::Timestamp};
+
+// Control packets start with these bytes, so they can be told apart from
+// application packets, which are sent as they are
+const MAGIC: [u8; 4] = [0xFF, 0xFE, b'N', b'S'];
+
+const TIME_PROBE: u8 = 1;
+const TIME_REPLY: u8 = 2;
+const PING: u8 = 3;
+const PONG: u8 = 4;
+const HEARTBEAT: u8 = 5;
+const GOODBYE: u8 = 6;
+
+// The length of the body of each reply, after the magic bytes & kind. The
+// requests they answer are padded to at least as long, & refused if shorter,
+// so that a Server can't be used to amplify traffic sent from a spoofed
+// address
+const TIME_REPLY_LEN: usize = 20;
+const PONG_LEN: usize = 8;
+const QUALITY_LEN: usize = 12;
+
+// Loss fractions are sent in hundredths of a percent
+const LOSS_SCALE: f32 = 10_000.0;
+
+/// A packet exchanged between Client & Server Sockets to run the socket layer
+/// itself, which is never delivered to the application. Application packets
+/// must not begin with the same 4 bytes as control packets, 0xFF 0xFE 'N' 'S'.
+/// Requests are never shorter than the replies they ask for
+#[derive(Clone, Debug, PartialEq)]
+pub enum ControlPacket {
+    /// Sent by a Client to measure the offset between its clock & the
+    /// Server's
+    TimeProbe {
+        /// Identifies the probe, so the Client can match up the reply
+        id: u32,
+    },
+    /// Sent by the Server in reply to a TimeProbe
+    TimeReply {
+        /// The id of the probe being replied to
+        id: u32,
+        /// When the Server received the probe
+        received: Timestamp,
+        /// When the Server sent the reply
+        sent: Timestamp,
+    },
+    /// Sent by a Client to measure the quality of the connection
+    Ping {
+        /// Identifies the ping, so the Client can match up the pong
+        id: u32,
+        /// The Client's measurements so far, so the Server knows them too
+        report: Option<ConnectionQuality>,
+    },
+    /// Sent by the Server in reply to a Ping
+    Pong {
+        /// The id of the ping being replied to
+        id: u32,
+        /// The number of pings the Server has received from the Client
+        pings_received: u32,
+    },
+    /// Sent by both sides to show they are still there
+    Heartbeat {
+        /// The Server's epoch, which changes whenever the Server restarts.
+        /// None when sent by a Client
+        epoch: Option<u64>,
+    },
+    /// Sent by either side when it disconnects
+    Goodbye,
+}
+
+impl ControlPacket {
+    /// Writes the packet, to be sent in place of an application packet
+    pub fn to_bytes(&self) -> Box<[u8]> {
+        let mut bytes = MAGIC.to_vec();
+        match self {
+            ControlPacket::TimeProbe { id } => {
+                bytes.push(TIME_PROBE);
+                bytes.extend_from_slice(&id.to_be_bytes());
+                pad(&mut bytes, TIME_REPLY_LEN);
+            }
+            ControlPacket::TimeReply { id, received, sent } => {
+                bytes.push(TIME_REPLY);
+                bytes.extend_from_slice(&id.to_be_bytes());
+                bytes.extend_from_slice(&received.to_bytes());
+                bytes.extend_from_slice(&sent.to_bytes());
+            }
+            ControlPacket::Ping { id, report } => {
+                bytes.push(PING);
+                bytes.extend_from_slice(&id.to_be_bytes());
+                if let Some(report) = report {
+                    write_quality(&mut bytes, report);
+                }
+                pad(&mut bytes, PONG_LEN);
+            }
+            ControlPacket::Pong { id, pings_received } => {
+                bytes.push(PONG);
+                bytes.extend_from_slice(&id.to_be_bytes());
+                bytes.extend_from_slice(&pings_received.to_be_bytes());
+            }
+            ControlPacket::Heartbeat { epoch } => {
+                bytes.push(HEARTBEAT);
+                if let Some(epoch) = epoch {
+                    bytes.extend_from_slice(&epoch.to_be_bytes());
+                }
+            }
+            ControlPacket::Goodbye => bytes.push(GOODBYE),
+        }
+        bytes.into_boxed_slice()
+    }
+
+    /// Reads a control packet, or returns None if the payload is an
+    /// application packet
+    pub fn from_bytes(payload: &[u8]) -> Option<ControlPacket> {
+        let body = payload.strip_prefix(&MAGIC[..])?;
+        let (kind, body) = body.split_first()?;
+        match *kind {
+            TIME_PROBE if body.len() >= TIME_REPLY_LEN => Some(ControlPacket::TimeProbe {
+                id: read_u32(body.get(0..4)?)?,
+            }),
+            TIME_REPLY => Some(ControlPacket::TimeReply {
+                id: read_u32(body.get(0..4)?)?,
+                received: read_timestamp(body.get(4..12)?)?,
+                sent: read_timestamp(body.get(12..20)?)?,
+            }),
+            PING if body.len() >= PONG_LEN => Some(ControlPacket::Ping {
+                id: read_u32(body.get(0..4)?)?,
+                report: match body.get(4..4 + QUALITY_LEN) {
+                    Some(report) => Some(read_quality(report)?),
+                    None => None,
+                },
+            }),
+            PONG => Some(ControlPacket::Pong {
+                id: read_u32(body.get(0..4)?)?,
+                pings_received: read_u32(body.get(4..8)?)?,
+            }),
+            HEARTBEAT => Some(ControlPacket::Heartbeat {
+                epoch: match body {
+                    [] => None,
+                    epoch => Some(u64::from_be_bytes(epoch.try_into().ok()?)),
+                },
+            }),
+            GOODBYE => Some(ControlPacket::Goodbye),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the payload is a control packet
+    pub fn is_control(payload: &[u8]) -> bool {
+        payload.starts_with(&MAGIC)
+    }
+}
+
+// Pads the body of a packet with zeroes, up to the given length
+fn pad(bytes: &mut Vec<u8>, body_len: usize) {
+    let len = MAGIC.len() + 1 + body_len;
+    if bytes.len() < len {
+        bytes.resize(len, 0);
+    }
+}
+
+fn read_timestamp(bytes: &[u8]) -> Option<Timestamp> {
+    Some(Timestamp::from_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8]) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u16(bytes: &[u8]) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.try_into().ok()?))
+}
+
+// Durations are sent in microseconds, which is precise enough for round trips
+fn write_quality(bytes: &mut Vec<u8>, quality: &ConnectionQuality) {
+    let micros = |duration: Duration| u32::try_from(duration.as_micros()).unwrap_or(u32::MAX);
+    let loss = |fraction: f32| (fraction.clamp(0.0, 1.0) * LOSS_SCALE).round() as u16;
+    bytes.extend_from_slice(&micros(quality.rtt).to_be_bytes());
+    bytes.extend_from_slice(&micros(quality.jitter).to_be_bytes());
+    bytes.extend_from_slice(&loss(quality.outbound_loss).to_be_bytes());
+    bytes.extend_from_slice(&loss(quality.inbound_loss).to_be_bytes());
+}
+
+fn read_quality(bytes: &[u8]) -> Option<ConnectionQuality> {
+    let micros = |bytes| Some(Duration::from_micros(u64::from(read_u32(bytes)?)));
+    let loss = |bytes| Some(f32::from(read_u16(bytes)?) / LOSS_SCALE);
+    Some(ConnectionQuality {
+        rtt: micros(bytes.get(0..4)?)?,
+        jitter: micros(bytes.get(4..8)?)?,
+        outbound_loss: loss(bytes.get(8..10)?)?,
+        inbound_loss: loss(bytes.get(10..12)?)?,
+        transport_rtt: None,
+    })
+}
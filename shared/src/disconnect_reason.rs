@@ -0,0 +1,12 @@
+/// Why the other side of a connection is considered disconnected
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DisconnectReason {
+    /// Nothing was heard from the other side within the idle timeout
+    TimedOut,
+    /// The other side said goodbye
+    Goodbye,
+    /// The underlying WebRTC session was closed
+    Closed,
+    /// The Server kicked the Client
+    Kicked,
+}
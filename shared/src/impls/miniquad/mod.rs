@@ -1,4 +1,3 @@
 pub mod instant;
 pub mod random;
-pub mod timer;
 pub mod timestamp;
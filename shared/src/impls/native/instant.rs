@@ -1,7 +1,10 @@
-use std::time::Duration;
+use std::{
+    ops::{Add, AddAssign, Sub, SubAssign},
+    time::Duration,
+};
 
 /// Represents a specific moment in time
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct Instant {
     inner: std::time::Instant,
 }
@@ -19,9 +22,38 @@ impl Instant {
         self.inner.elapsed()
     }
 
-    /// Returns time until the Instant occurs
+    /// Returns time until the Instant occurs, or zero if it has passed
     pub fn until(&self) -> Duration {
-        return self.inner.duration_since(std::time::Instant::now());
+        self.inner
+            .saturating_duration_since(std::time::Instant::now())
+    }
+
+    /// Returns time elapsed from the given earlier Instant to this one, or
+    /// zero if it is later
+    pub fn duration_since(&self, earlier: &Instant) -> Duration {
+        self.inner.saturating_duration_since(earlier.inner)
+    }
+
+    /// Returns time elapsed from the given earlier Instant to this one, or
+    /// None if it is later
+    pub fn checked_duration_since(&self, earlier: &Instant) -> Option<Duration> {
+        self.inner.checked_duration_since(earlier.inner)
+    }
+
+    /// Returns the Instant the given Duration after this one, or None if it
+    /// can't be represented
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        self.inner
+            .checked_add(duration)
+            .map(|inner| Instant { inner })
+    }
+
+    /// Returns the Instant the given Duration before this one, or None if it
+    /// can't be represented
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        self.inner
+            .checked_sub(duration)
+            .map(|inner| Instant { inner })
     }
 
     /// Adds a given number of milliseconds to the Instant
@@ -29,8 +61,72 @@ impl Instant {
         self.inner += Duration::from_millis(millis.into());
     }
 
+    /// Adds a given number of microseconds to the Instant
+    pub fn add_micros(&mut self, micros: u64) {
+        self.inner += Duration::from_micros(micros);
+    }
+
     /// Returns inner Instant implementation
     pub fn inner(&self) -> std::time::Instant {
         return self.inner.clone();
     }
 }
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, duration: Duration) -> Instant {
+        self.checked_add(duration)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, duration: Duration) {
+        self.inner += duration;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, duration: Duration) -> Instant {
+        self.checked_sub(duration)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, duration: Duration) {
+        self.inner -= duration;
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    /// Returns time elapsed between the Instants, or zero if the right hand
+    /// side is later
+    fn sub(self, earlier: Instant) -> Duration {
+        self.duration_since(&earlier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Instant;
+
+    #[test]
+    fn arithmetic_saturates_or_checks() {
+        let now = Instant::now();
+        let later = now.clone() + Duration::from_millis(1500);
+        assert_eq!(later.clone() - now.clone(), Duration::from_millis(1500));
+        assert_eq!(now.clone() - later.clone(), Duration::ZERO);
+        assert_eq!(now.checked_duration_since(&later), None);
+        assert_eq!(later.clone() - Duration::from_millis(1500), now);
+        assert!(later.until() > Duration::from_secs(1));
+        assert_eq!(now.until(), Duration::ZERO);
+    }
+}
@@ -1,32 +1,81 @@
-use js_sys::Date;
-use std::{cmp::Ordering, time::Duration};
+use std::{
+    cmp::Ordering,
+    ops::{Add, AddAssign, Sub, SubAssign},
+    time::Duration,
+};
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    // performance.now() is monotonic, unlike Date.now(), which follows the
+    // system clock
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn performance_now() -> f64;
+}
+
+fn now_millis() -> f64 {
+    performance_now()
+}
 
 /// Represents a specific moment in time
-#[derive(Clone, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct Instant {
+    // Milliseconds since the page's time origin
     inner: f64,
 }
 
 impl Instant {
     /// Creates an Instant from the moment the method is called
     pub fn now() -> Self {
-        Instant { inner: Date::now() }
+        Instant {
+            inner: now_millis(),
+        }
     }
 
     /// Returns time elapsed since the Instant
     pub fn elapsed(&self) -> Duration {
-        let inner_duration = Date::now() - self.inner;
-        let seconds: u64 = (inner_duration as u64) / 1000;
-        let nanos: u32 = ((inner_duration as u32) % 1000) * 1000000;
-        return Duration::new(seconds, nanos);
+        millis_to_duration(now_millis() - self.inner)
     }
 
-    /// Returns time until the Instant occurs
+    /// Returns time until the Instant occurs, or zero if it has passed
     pub fn until(&self) -> Duration {
-        let inner_duration = self.inner - Date::now();
-        let seconds: u64 = (inner_duration as u64) / 1000;
-        let nanos: u32 = ((inner_duration as u32) % 1000) * 1000000;
-        return Duration::new(seconds, nanos);
+        millis_to_duration(self.inner - now_millis())
+    }
+
+    /// Returns time elapsed from the given earlier Instant to this one, or
+    /// zero if it is later
+    pub fn duration_since(&self, earlier: &Instant) -> Duration {
+        millis_to_duration(self.inner - earlier.inner)
+    }
+
+    /// Returns time elapsed from the given earlier Instant to this one, or
+    /// None if it is later
+    pub fn checked_duration_since(&self, earlier: &Instant) -> Option<Duration> {
+        if self.inner < earlier.inner {
+            return None;
+        }
+        Some(millis_to_duration(self.inner - earlier.inner))
+    }
+
+    /// Returns the Instant the given Duration after this one, or None if it
+    /// can't be represented
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        let inner = self.inner + duration.as_secs_f64() * 1000.0;
+        if !inner.is_finite() {
+            return None;
+        }
+        Some(Instant { inner })
+    }
+
+    /// Returns the Instant the given Duration before this one, or None if it
+    /// would be before the page's time origin
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        let inner = self.inner - duration.as_secs_f64() * 1000.0;
+        if inner < 0.0 {
+            return None;
+        }
+        Some(Instant { inner })
     }
 
     /// Adds a given number of milliseconds to the Instant
@@ -34,6 +83,11 @@ impl Instant {
         let millis_f64: f64 = millis.into();
         self.inner += millis_f64;
     }
+
+    /// Adds a given number of microseconds to the Instant
+    pub fn add_micros(&mut self, micros: u64) {
+        self.inner += micros as f64 / 1000.0;
+    }
 }
 
 impl Eq for Instant {}
@@ -49,3 +103,48 @@ impl Ord for Instant {
         }
     }
 }
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, duration: Duration) -> Instant {
+        self.checked_add(duration)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = self.clone() + duration;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, duration: Duration) -> Instant {
+        self.checked_sub(duration)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, duration: Duration) {
+        *self = self.clone() - duration;
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    /// Returns time elapsed between the Instants, or zero if the right hand
+    /// side is later
+    fn sub(self, earlier: Instant) -> Duration {
+        self.duration_since(&earlier)
+    }
+}
+
+// Negative spans saturate to zero
+fn millis_to_duration(millis: f64) -> Duration {
+    Duration::from_secs_f64(millis.max(0.0) / 1000.0)
+}
@@ -1,27 +1,6 @@
 use js_sys::Date;
 
-/// A Timestamp for a moment in time that can be read/written to/from a byte
-/// stream
-#[derive(Copy, Clone, PartialEq)]
-pub struct Timestamp {
-    time: u64,
-}
-
-impl Timestamp {
-    /// Get a Timestamp for the current moment
-    pub fn now() -> Self {
-        Timestamp {
-            time: Date::now() as u64,
-        }
-    }
-
-    /// Convert to u64
-    pub fn to_u64(&self) -> u64 {
-        self.time
-    }
-
-    /// Convert from u64
-    pub fn from_u64(value: &u64) -> Self {
-        Self { time: *value }
-    }
+/// Returns the number of milliseconds since the Unix epoch
+pub fn unix_millis() -> u64 {
+    Date::now() as u64
 }
@@ -0,0 +1,206 @@
+use std::{
+    error::Error,
+    fmt,
+    net::IpAddr,
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+
+/// A block of IPv4 or IPv6 addresses in CIDR notation, e.g. "10.0.0.0/8"
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CidrBlock {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl CidrBlock {
+    /// Creates a new CidrBlock from a base address & prefix length
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Self, CidrParseError> {
+        let max_prefix_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if prefix_len > max_prefix_len {
+            return Err(CidrParseError(format!(
+                "prefix length {} is too long for {}",
+                prefix_len, addr
+            )));
+        }
+        Ok(CidrBlock { addr, prefix_len })
+    }
+
+    /// Returns whether the given address is within the block
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.addr, normalize(ip)) {
+            (IpAddr::V4(block), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(block) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(block), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(block) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for CidrBlock {
+    type Err = CidrParseError;
+
+    /// Parses a block such as "192.168.0.0/16" or "2001:db8::/32". A bare
+    /// address is treated as a block containing only that address
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CidrParseError(format!("'{}' is not a valid CIDR block", s));
+        let (addr_str, prefix_len) = match s.trim().split_once('/') {
+            Some((addr_str, prefix_str)) => (
+                addr_str,
+                Some(prefix_str.parse::<u8>().map_err(|_| invalid())?),
+            ),
+            None => (s.trim(), None),
+        };
+        let addr = addr_str.parse::<IpAddr>().map_err(|_| invalid())?;
+        let prefix_len = prefix_len.unwrap_or(match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        });
+        CidrBlock::new(addr, prefix_len)
+    }
+}
+
+impl fmt::Display for CidrBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+/// An error returned when a CidrBlock is invalid
+#[derive(Debug)]
+pub struct CidrParseError(String);
+
+impl fmt::Display for CidrParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for CidrParseError {}
+
+/// Allow & deny lists of CidrBlocks, used by the Server Socket to refuse
+/// remote addresses. Clones share the same lists, so they can be updated at
+/// runtime through any clone
+#[derive(Clone, Default)]
+pub struct IpFilter {
+    lists: Arc<RwLock<IpFilterLists>>,
+}
+
+#[derive(Default)]
+struct IpFilterLists {
+    allow: Vec<CidrBlock>,
+    deny: Vec<CidrBlock>,
+}
+
+impl IpFilter {
+    /// Creates a new IpFilter which allows every address
+    pub fn new() -> Self {
+        IpFilter::default()
+    }
+
+    /// Adds a block to the allow list. Once the allow list is non-empty, only
+    /// addresses within it are allowed
+    pub fn allow(&self, block: CidrBlock) {
+        self.lists.write().unwrap().allow.push(block);
+    }
+
+    /// Adds a block to the deny list. Addresses within it are refused, even if
+    /// they are also in the allow list
+    pub fn deny(&self, block: CidrBlock) {
+        self.lists.write().unwrap().deny.push(block);
+    }
+
+    /// Removes a block from the allow list
+    pub fn remove_allow(&self, block: &CidrBlock) {
+        self.lists.write().unwrap().allow.retain(|b| b != block);
+    }
+
+    /// Removes a block from the deny list
+    pub fn remove_deny(&self, block: &CidrBlock) {
+        self.lists.write().unwrap().deny.retain(|b| b != block);
+    }
+
+    /// Replaces the allow list
+    pub fn set_allow_list(&self, blocks: Vec<CidrBlock>) {
+        self.lists.write().unwrap().allow = blocks;
+    }
+
+    /// Replaces the deny list
+    pub fn set_deny_list(&self, blocks: Vec<CidrBlock>) {
+        self.lists.write().unwrap().deny = blocks;
+    }
+
+    /// Gets whether the given address passes the allow & deny lists
+    pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+        let lists = self.lists.read().unwrap();
+        if lists.deny.iter().any(|block| block.contains(ip)) {
+            return false;
+        }
+        lists.allow.is_empty() || lists.allow.iter().any(|block| block.contains(ip))
+    }
+}
+
+// Treat IPv4-mapped IPv6 addresses (::ffff:a.b.c.d) as the IPv4 address
+fn normalize(ip: &IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => IpAddr::V4(v4),
+            None => *ip,
+        },
+        IpAddr::V4(_) => *ip,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::{CidrBlock, IpFilter};
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn cidr_block_contains() {
+        let block: CidrBlock = "10.1.0.0/16".parse().unwrap();
+        assert!(block.contains(&ip("10.1.200.3")));
+        assert!(!block.contains(&ip("10.2.0.1")));
+        assert!(block.contains(&ip("::ffff:10.1.0.9")));
+
+        let block: CidrBlock = "2001:db8::/32".parse().unwrap();
+        assert!(block.contains(&ip("2001:db8:1::1")));
+        assert!(!block.contains(&ip("2001:db9::1")));
+
+        let everything: CidrBlock = "0.0.0.0/0".parse().unwrap();
+        assert!(everything.contains(&ip("203.0.113.7")));
+
+        assert!("10.0.0.0/33".parse::<CidrBlock>().is_err());
+        assert!("not an ip".parse::<CidrBlock>().is_err());
+    }
+
+    #[test]
+    fn ip_filter_deny_wins_over_allow() {
+        let filter = IpFilter::new();
+        assert!(filter.is_allowed(&ip("198.51.100.1")));
+
+        filter.allow("192.168.0.0/16".parse().unwrap());
+        filter.deny("192.168.66.0/24".parse().unwrap());
+
+        assert!(filter.is_allowed(&ip("192.168.1.1")));
+        assert!(!filter.is_allowed(&ip("192.168.66.1")));
+        assert!(!filter.is_allowed(&ip("198.51.100.1")));
+    }
+}
@@ -0,0 +1,28 @@
+use std::{default::Default, time::Duration};
+
+/// Contains configuration for detecting when the other side of a connection
+/// has gone away. Must be set on both the Client & the Server Socket
+#[derive(Clone)]
+pub struct KeepaliveConfig {
+    /// How often each side sends a heartbeat to the other
+    pub heartbeat_interval: Duration,
+    /// How long to go without hearing from the other side before it is
+    /// considered disconnected
+    pub idle_timeout: Duration,
+}
+
+impl KeepaliveConfig {
+    /// Creates a new KeepaliveConfig
+    pub fn new(heartbeat_interval: Duration, idle_timeout: Duration) -> Self {
+        KeepaliveConfig {
+            heartbeat_interval,
+            idle_timeout,
+        }
+    }
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        KeepaliveConfig::new(Duration::from_secs(1), Duration::from_secs(10))
+    }
+}
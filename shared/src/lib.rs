@@ -16,16 +16,55 @@ extern crate cfg_if;
 /// conditions
 pub mod link_condition_logic;
 
+mod clock;
+mod condition_schedule;
+mod connection_quality;
+mod connection_quality_config;
+mod control_packet;
+mod disconnect_reason;
 mod impls;
+mod ip_filter;
+mod keepalive_config;
 mod link_conditioner_config;
+mod link_conditioner_handle;
+mod loopback;
+mod rate_limit_config;
+mod seeded_random;
+mod signaling_config;
 mod socket_config;
+mod ticker;
 mod time_queue;
+mod time_sync;
+mod time_sync_config;
+mod timer;
+mod timestamp;
 mod url_parse;
 
-pub use impls::{Instant, Random, Timer, Timestamp};
-pub use link_conditioner_config::LinkConditionerConfig;
+pub use clock::{Clock, ManualClock, RealClock, SharedClock};
+pub use condition_schedule::{ConditionSchedule, ScheduleParseError, ScheduledConditions};
+pub use connection_quality::{ConnectionQuality, QualityEstimator};
+pub use connection_quality_config::ConnectionQualityConfig;
+pub use control_packet::ControlPacket;
+pub use disconnect_reason::DisconnectReason;
+pub use impls::{Instant, Random};
+pub use ip_filter::{CidrBlock, CidrParseError, IpFilter};
+pub use keepalive_config::KeepaliveConfig;
+pub use link_condition_logic::{ConditionedPacket, LinkConditioner, LinkDirection};
+pub use link_conditioner_config::{
+    JitterDistribution, LinkConditionerConfig, LossModel, QueueLimit,
+};
+pub use link_conditioner_handle::{LinkConditionerHandle, LinkConditionerStats};
+pub use loopback::{LoopbackClient, LoopbackHub};
+pub use rate_limit_config::RateLimitConfig;
+pub use seeded_random::SeededRandom;
+pub use signaling_config::SignalingConfig;
 pub use socket_config::SocketConfig;
-pub use time_queue::TimeQueue;
+pub use ticker::Ticker;
+pub use time_queue::{DrainReady, TimeQueue};
+pub use time_sync::TimeSync;
+pub use time_sync_config::TimeSyncConfig;
+pub use timer::Timer;
+pub use timestamp::Timestamp;
 pub use url_parse::{parse_server_url, url_to_socket_addr};
 
 cfg_if! {
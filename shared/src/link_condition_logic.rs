@@ -1,31 +1,816 @@
 extern crate log;
-use log::info;
+use log::{debug, trace};
 
-use super::{link_conditioner_config::LinkConditionerConfig, time_queue::TimeQueue, Instant};
-use crate::Random;
+use std::{collections::VecDeque, net::SocketAddr, time::Duration};
 
-/// Given a config object which describes the network conditions to be
-/// simulated, process an incoming packet, adding it to a TimeQueue at the
-/// correct timestamp
-pub fn process_packet<T: Eq>(
+use super::{
+    clock::{real_clock, SharedClock},
+    condition_schedule::{ConditionSchedule, ScheduledConditions},
+    link_conditioner_config::{JitterDistribution, LinkConditionerConfig, LossModel, QueueLimit},
+    link_conditioner_handle::LinkConditionerHandle,
+    seeded_random::SeededRandom,
+    time_queue::TimeQueue,
+    Instant,
+};
+
+// A reordered packet is delivered anyway once it has been held this long
+const REORDER_TIMEOUT_MS: u32 = 1000;
+// Pareto jitter is capped at this multiple of the configured jitter, so the
+// tail stays within a plausible range
+const MAX_PARETO_JITTER_FACTOR: f32 = 100.0;
+
+/// The direction of the packets which a LinkConditioner processes
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LinkDirection {
+    /// Packets received from the remote host
+    Incoming,
+    /// Packets sent to the remote host
+    Outgoing,
+}
+
+/// A packet which can be processed by a LinkConditioner
+pub trait ConditionedPacket: Clone + Eq {
+    /// Gets the payload of the packet
+    fn payload(&self) -> &[u8];
+    /// Gets the payload of the packet, to be corrupted
+    fn payload_mut(&mut self) -> &mut [u8];
+}
+
+impl ConditionedPacket for Box<[u8]> {
+    fn payload(&self) -> &[u8] {
+        self
+    }
+
+    fn payload_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl ConditionedPacket for (SocketAddr, Box<[u8]>) {
+    fn payload(&self) -> &[u8] {
+        &self.1
+    }
+
+    fn payload_mut(&mut self) -> &mut [u8] {
+        &mut self.1
+    }
+}
+
+/// Simulates the network conditions described by a LinkConditionerConfig,
+/// for packets travelling in one direction
+pub struct LinkConditioner<P: ConditionedPacket> {
+    config: LinkConditionerConfig,
+    direction: LinkDirection,
+    handle: LinkConditionerHandle,
+    follows_handle: bool,
+    generation: u64,
+    time_queue: TimeQueue<P>,
+    reordered: Vec<ReorderedPacket<P>>,
+    bandwidth_queue: BandwidthQueue,
+    bad_state: bool,
+    schedule_start: Instant,
+    trace_index: usize,
+    random: SeededRandom,
+    clock: SharedClock,
+}
+
+#[derive(Clone)]
+struct ReorderedPacket<P> {
+    packet: P,
+    deliver_at: Instant,
+    held_since: Instant,
+    remaining: u32,
+}
+
+// The conditions which apply to a single direction
+struct Conditions {
+    latency: u32,
+    jitter: u32,
+    loss: f32,
+    duplicate: f32,
+    corruption: f32,
+    bit_flip: f32,
+    reorder: f32,
+    bandwidth: Option<u32>,
+}
+
+// Packets waiting to be serialized onto a bandwidth limited link
+#[derive(Clone, Default)]
+struct BandwidthQueue {
+    link_free_at: Option<Instant>,
+    queued: VecDeque<(Instant, u32)>,
+    queued_bytes: u32,
+}
+
+impl<P: ConditionedPacket> LinkConditioner<P> {
+    /// Creates a new LinkConditioner for packets travelling in the given
+    /// direction
+    pub fn new(config: &LinkConditionerConfig, direction: LinkDirection) -> Self {
+        let handle = LinkConditionerHandle::without_logging(config.clone());
+        LinkConditioner {
+            config: config.clone(),
+            direction,
+            generation: handle.generation(),
+            handle,
+            follows_handle: false,
+            time_queue: TimeQueue::new(),
+            reordered: Vec::new(),
+            bandwidth_queue: BandwidthQueue::default(),
+            bad_state: false,
+            schedule_start: Instant::now(),
+            trace_index: 0,
+            random: random_for(config.seed, direction),
+            clock: real_clock(),
+        }
+    }
+
+    /// Creates a new LinkConditioner which simulates the config of the given
+    /// LinkConditionerHandle, picking up any changes made through it, &
+    /// reports its statistics to it
+    pub fn from_handle(handle: &LinkConditionerHandle, direction: LinkDirection) -> Self {
+        let mut conditioner = LinkConditioner::new(&handle.config(), direction);
+        conditioner.handle = handle.clone();
+        conditioner.follows_handle = true;
+        conditioner.generation = handle.generation();
+        conditioner
+    }
+
+    /// Reports statistics to the given LinkConditionerHandle, while keeping
+    /// this LinkConditioner's own config. Used when several LinkConditioners
+    /// with different configs share one set of statistics
+    pub fn reporting_to(mut self, handle: &LinkConditionerHandle) -> Self {
+        // Packets already queued now count towards the new handle's depth
+        let queued = self.queued();
+        self.handle.counters(self.direction).discarded(queued);
+        handle.counters(self.direction).queued(queued);
+        self.handle = handle.clone();
+        self.follows_handle = false;
+        self
+    }
+
+    /// Reads the time from the given Clock, rather than the wall clock. Used
+    /// with a ManualClock to simulate conditions without waiting for them
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.time_queue.set_clock(clock.clone());
+        self.schedule_start = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Gets the LinkConditionerHandle which receives this LinkConditioner's
+    /// statistics
+    pub fn handle(&self) -> &LinkConditionerHandle {
+        &self.handle
+    }
+
+    /// Replaces the conditions being simulated. Packets which are already
+    /// queued keep the timestamp they were given
+    pub fn set_config(&mut self, config: &LinkConditionerConfig) {
+        self.replace_config(config.clone());
+    }
+
+    /// Gets the conditions being simulated
+    pub fn config(&self) -> &LinkConditionerConfig {
+        &self.config
+    }
+
+    /// Process a packet, dropping, corrupting or duplicating it & adding it to
+    /// the queue at the timestamp it should be delivered
+    pub fn process_packet(&mut self, mut packet: P) {
+        self.refresh_config();
+        let mut conditions = self.conditions();
+        self.handle.counters(self.direction).processed();
+
+        let lost = match self.next_trace_delay() {
+            Some(Some(delay)) => {
+                conditions.latency = delay;
+                conditions.jitter = 0;
+                false
+            }
+            Some(None) => true,
+            None => self.is_lost(conditions.loss),
+        };
+        if lost {
+            // drop the packet
+            trace!("link conditioner: packet lost");
+            self.handle.counters(self.direction).dropped();
+            return;
+        }
+
+        let corrupted = chance(&mut self.random, conditions.corruption)
+            && corrupt_byte(&mut self.random, packet.payload_mut());
+        let flipped = chance(&mut self.random, conditions.bit_flip)
+            && flip_bit(&mut self.random, packet.payload_mut());
+        if corrupted || flipped {
+            self.handle.counters(self.direction).corrupted();
+        }
+
+        if chance(&mut self.random, conditions.duplicate) {
+            self.handle.counters(self.direction).duplicated();
+            self.schedule_packet(&conditions, packet.clone());
+        }
+        self.schedule_packet(&conditions, packet);
+    }
+
+    /// Returns whether or not there is a packet ready to be delivered
+    pub fn has_item(&self) -> bool {
+        let now = self.clock.now();
+        self.time_queue.has_item() || self.reordered.iter().any(|held| held.timed_out(&now))
+    }
+
+    /// Pops a packet which is ready to be delivered, if any
+    pub fn pop_item(&mut self) -> Option<P> {
+        self.release_timed_out();
+        let packet = self.time_queue.pop_item();
+        if packet.is_some() {
+            self.handle.counters(self.direction).delivered();
+        }
+        packet
+    }
+
+    /// Returns whether no packets are queued or held back
+    pub fn is_empty(&self) -> bool {
+        self.time_queue.is_empty() && self.reordered.is_empty()
+    }
+
+    // The number of packets queued or held back
+    fn queued(&self) -> u64 {
+        (self.time_queue.len() + self.reordered.len()) as u64
+    }
+
+    /// Returns the Instant at which the next packet will be ready to be
+    /// delivered, if any are queued
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let queued = self.time_queue.next_deadline();
+        let held = self.reordered.iter().map(|held| held.timeout_instant());
+        queued.into_iter().chain(held).min()
+    }
+
+    // Pick up any config changes made through the LinkConditionerHandle
+    fn refresh_config(&mut self) {
+        if !self.follows_handle {
+            return;
+        }
+        let generation = self.handle.generation();
+        if generation != self.generation {
+            self.generation = generation;
+            self.replace_config(self.handle.config());
+        }
+    }
+
+    // A new schedule starts from the beginning
+    fn replace_config(&mut self, config: LinkConditionerConfig) {
+        if config.seed.is_some() && config.seed != self.config.seed {
+            self.random = random_for(config.seed, self.direction);
+        }
+        let schedule_changed = match (self.schedule(), schedule_for(&config, self.direction)) {
+            (Some(old), Some(new)) => !old.same_as(new),
+            (None, None) => false,
+            _ => true,
+        };
+        if schedule_changed {
+            self.schedule_start = self.clock.now();
+            self.trace_index = 0;
+        }
+        self.config = config;
+    }
+
+    fn schedule(&self) -> Option<&ConditionSchedule> {
+        schedule_for(&self.config, self.direction)
+    }
+
+    // The delay of the next packet, if a trace is being replayed
+    fn next_trace_delay(&mut self) -> Option<Option<u32>> {
+        let delay = self.schedule()?.trace_delay(self.trace_index);
+        self.trace_index += 1;
+        delay
+    }
+
+    fn conditions(&self) -> Conditions {
+        let config = &self.config;
+        let mut conditions = match self.direction {
+            LinkDirection::Incoming => Conditions {
+                latency: config.incoming_latency,
+                jitter: config.incoming_jitter,
+                loss: config.incoming_loss,
+                duplicate: config.incoming_duplicate,
+                corruption: config.incoming_corruption,
+                bit_flip: config.incoming_bit_flip,
+                reorder: config.incoming_reorder,
+                bandwidth: config.incoming_bandwidth,
+            },
+            LinkDirection::Outgoing => Conditions {
+                latency: config.outgoing_latency,
+                jitter: config.outgoing_jitter,
+                loss: config.outgoing_loss,
+                duplicate: config.outgoing_duplicate,
+                corruption: config.outgoing_corruption,
+                bit_flip: config.outgoing_bit_flip,
+                reorder: config.outgoing_reorder,
+                bandwidth: config.outgoing_bandwidth,
+            },
+        };
+
+        if let Some(schedule) = self.schedule() {
+            let base = ScheduledConditions {
+                latency: conditions.latency,
+                jitter: conditions.jitter,
+                loss: conditions.loss,
+            };
+            if let Some(scheduled) =
+                schedule.conditions_at(self.clock.elapsed(&self.schedule_start), base)
+            {
+                conditions.latency = scheduled.latency;
+                conditions.jitter = scheduled.jitter;
+                conditions.loss = scheduled.loss;
+            }
+        }
+
+        conditions
+    }
+
+    fn is_lost(&mut self, loss: f32) -> bool {
+        match self.config.loss_model {
+            LossModel::Uniform => chance(&mut self.random, loss),
+            LossModel::GilbertElliott {
+                good_to_bad,
+                bad_to_good,
+                bad_loss,
+            } => {
+                if self.bad_state {
+                    self.bad_state = !chance(&mut self.random, bad_to_good);
+                } else {
+                    self.bad_state = chance(&mut self.random, good_to_bad);
+                }
+                chance(
+                    &mut self.random,
+                    if self.bad_state { bad_loss } else { loss },
+                )
+            }
+        }
+    }
+
+    fn schedule_packet(&mut self, conditions: &Conditions, packet: P) {
+        let jitter = jitter_offset(
+            &mut self.random,
+            self.config.jitter_distribution,
+            conditions.jitter,
+        );
+        // Latency can never be negative, however large the jitter
+        let latency_millis = (conditions.latency as f32 + jitter).max(0.0);
+        let mut packet_timestamp = match conditions.bandwidth {
+            Some(bandwidth) => {
+                let bytes = packet.payload().len() as u32;
+                match self.bandwidth_queue.enqueue(
+                    self.clock.now(),
+                    bandwidth,
+                    self.config.queue_limit,
+                    bytes,
+                ) {
+                    Some(departure) => departure,
+                    None => {
+                        trace!("link conditioner: queue full, packet dropped");
+                        self.handle.counters(self.direction).dropped();
+                        return;
+                    }
+                }
+            }
+            None => self.clock.now(),
+        };
+        let latency_micros = (latency_millis * 1000.0) as u64;
+        let delay = self.clock.until(&packet_timestamp) + Duration::from_micros(latency_micros);
+        packet_timestamp.add_micros(latency_micros);
+        self.handle.counters(self.direction).scheduled(delay);
+
+        if chance(&mut self.random, conditions.reorder) {
+            self.handle.counters(self.direction).reordered();
+            self.reordered.push(ReorderedPacket {
+                packet,
+                deliver_at: packet_timestamp,
+                held_since: self.clock.now(),
+                remaining: self.config.reorder_distance.max(1),
+            });
+            return;
+        }
+
+        // Held packets are delivered after the packets which overtake them.
+        // The overtaking packet is queued first, so it is popped first when
+        // their deadlines are the same
+        let mut released = Vec::new();
+        let mut index = 0;
+        while index < self.reordered.len() {
+            self.reordered[index].remaining -= 1;
+            if self.reordered[index].remaining == 0 {
+                released.push(self.reordered.remove(index));
+            } else {
+                index += 1;
+            }
+        }
+
+        self.time_queue.add_item(packet_timestamp.clone(), packet);
+        for held in released {
+            let deliver_at = held.deliver_at.max(packet_timestamp.clone());
+            self.time_queue.add_item(deliver_at, held.packet);
+        }
+    }
+
+    fn release_timed_out(&mut self) {
+        let now = self.clock.now();
+        let mut index = 0;
+        while index < self.reordered.len() {
+            if self.reordered[index].timed_out(&now) {
+                let held = self.reordered.remove(index);
+                self.time_queue.add_item(held.deliver_at, held.packet);
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+// A clone delivers its own copy of every queued packet, so they count towards
+// the queue depth twice
+impl<P: ConditionedPacket> Clone for LinkConditioner<P> {
+    fn clone(&self) -> Self {
+        self.handle.counters(self.direction).queued(self.queued());
+        LinkConditioner {
+            config: self.config.clone(),
+            direction: self.direction,
+            handle: self.handle.clone(),
+            follows_handle: self.follows_handle,
+            generation: self.generation,
+            time_queue: self.time_queue.clone(),
+            reordered: self.reordered.clone(),
+            bandwidth_queue: self.bandwidth_queue.clone(),
+            bad_state: self.bad_state,
+            schedule_start: self.schedule_start.clone(),
+            trace_index: self.trace_index,
+            random: self.random.clone(),
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+// Packets still queued when a LinkConditioner is dropped will never be
+// delivered, so no longer count towards the queue depth
+impl<P: ConditionedPacket> Drop for LinkConditioner<P> {
+    fn drop(&mut self) {
+        self.handle
+            .counters(self.direction)
+            .discarded(self.queued());
+    }
+}
+
+fn schedule_for(
     config: &LinkConditionerConfig,
-    time_queue: &mut TimeQueue<T>,
-    packet: T,
-) {
-    if Random::gen_range_f32(0.0, 1.0) <= config.incoming_loss {
-        // drop the packet
-        info!("link conditioner: packet lost");
-        return;
-    }
-    let mut latency: u32 = config.incoming_latency;
-    if config.incoming_jitter > 0 {
-        if Random::gen_bool() {
-            latency += Random::gen_range_u32(0, config.incoming_jitter);
-        } else {
-            latency -= Random::gen_range_u32(0, config.incoming_jitter);
-        }
-    }
-    let mut packet_timestamp = Instant::now();
-    packet_timestamp.add_millis(latency);
-    time_queue.add_item(packet_timestamp, packet);
+    direction: LinkDirection,
+) -> Option<&ConditionSchedule> {
+    match direction {
+        LinkDirection::Incoming => config.incoming_schedule.as_ref(),
+        LinkDirection::Outgoing => config.outgoing_schedule.as_ref(),
+    }
+}
+
+impl BandwidthQueue {
+    // Returns the Instant at which the packet has been fully transmitted, or
+    // None if the queue is full
+    fn enqueue(
+        &mut self,
+        now: Instant,
+        bandwidth: u32,
+        queue_limit: QueueLimit,
+        bytes: u32,
+    ) -> Option<Instant> {
+        while let Some((departure, queued_bytes)) = self.queued.front() {
+            if *departure > now {
+                break;
+            }
+            self.queued_bytes -= queued_bytes;
+            self.queued.pop_front();
+        }
+
+        let full = match queue_limit {
+            QueueLimit::Unlimited => false,
+            QueueLimit::Bytes(max_bytes) => self.queued_bytes + bytes > max_bytes,
+            QueueLimit::Packets(max_packets) => self.queued.len() as u32 >= max_packets,
+        };
+        if full {
+            return None;
+        }
+
+        let mut departure = match &self.link_free_at {
+            Some(link_free_at) if *link_free_at > now => link_free_at.clone(),
+            _ => now,
+        };
+        departure.add_micros(u64::from(bytes) * 8 * 1_000_000 / u64::from(bandwidth.max(1)));
+        self.link_free_at = Some(departure.clone());
+        self.queued.push_back((departure.clone(), bytes));
+        self.queued_bytes += bytes;
+        Some(departure)
+    }
+}
+
+impl<P> ReorderedPacket<P> {
+    fn timeout_instant(&self) -> Instant {
+        let mut timeout = self.held_since.clone();
+        timeout.add_millis(REORDER_TIMEOUT_MS);
+        timeout
+    }
+
+    fn timed_out(&self, now: &Instant) -> bool {
+        self.timeout_instant() <= *now
+    }
+}
+
+// Incoming & outgoing conditioners with the same seed use different sequences
+fn random_for(seed: Option<u64>, direction: LinkDirection) -> SeededRandom {
+    let random = match seed {
+        Some(seed) => SeededRandom::new(seed),
+        None => SeededRandom::from_entropy(),
+    };
+    debug!(
+        "link conditioner: {:?} seed is {}",
+        direction,
+        random.seed()
+    );
+    match direction {
+        LinkDirection::Incoming => random,
+        LinkDirection::Outgoing => SeededRandom::new(!random.seed()),
+    }
+}
+
+// Returns a random offset in milliseconds to be added to the latency
+fn jitter_offset(random: &mut SeededRandom, distribution: JitterDistribution, jitter: u32) -> f32 {
+    if jitter == 0 {
+        return 0.0;
+    }
+    let jitter = jitter as f32;
+    match distribution {
+        JitterDistribution::Uniform => random.gen_range_f32(-jitter, jitter),
+        JitterDistribution::Normal => {
+            // Box-Muller transform, with u1 in (0, 1] to avoid ln(0)
+            let u1 = 1.0 - random.gen_range_f32(0.0, 1.0);
+            let u2 = random.gen_range_f32(0.0, 1.0);
+            let standard_normal = (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos();
+            standard_normal * jitter
+        }
+        JitterDistribution::Pareto { shape } => {
+            let u = 1.0 - random.gen_range_f32(0.0, 1.0);
+            let spike = u.powf(-1.0 / shape.max(f32::EPSILON)) - 1.0;
+            jitter * spike.min(MAX_PARETO_JITTER_FACTOR)
+        }
+    }
+}
+
+fn chance(random: &mut SeededRandom, probability: f32) -> bool {
+    probability > 0.0 && random.gen_range_f32(0.0, 1.0) < probability
+}
+
+// Overwrites a random byte with a different value
+fn corrupt_byte(random: &mut SeededRandom, payload: &mut [u8]) -> bool {
+    if payload.is_empty() {
+        return false;
+    }
+    trace!("link conditioner: packet corrupted");
+    let index = random.gen_range_u32(0, payload.len() as u32) as usize;
+    payload[index] ^= random.gen_range_u32(1, 256) as u8;
+    true
+}
+
+// Flips a single random bit
+fn flip_bit(random: &mut SeededRandom, payload: &mut [u8]) -> bool {
+    if payload.is_empty() {
+        return false;
+    }
+    trace!("link conditioner: bit flipped");
+    let bit = random.gen_range_u32(0, payload.len() as u32 * 8) as usize;
+    payload[bit / 8] ^= 1 << (bit % 8);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::{LinkConditioner, LinkDirection};
+    use crate::{
+        ConditionSchedule, LinkConditionerConfig, LinkConditionerHandle, LossModel, ManualClock,
+        QueueLimit,
+    };
+
+    fn packet() -> Box<[u8]> {
+        vec![0b1010_1010; 16].into_boxed_slice()
+    }
+
+    #[test]
+    fn duplicates_and_flips_bits() {
+        let config = LinkConditionerConfig::default().with_incoming_effects(1.0, 0.0, 1.0, 0.0);
+        let mut conditioner = LinkConditioner::new(&config, LinkDirection::Incoming);
+        conditioner.process_packet(packet());
+
+        let first = conditioner.pop_item().unwrap();
+        let second = conditioner.pop_item().unwrap();
+        assert!(conditioner.pop_item().is_none());
+        assert_eq!(first, second);
+
+        let flipped_bits: u32 = first
+            .iter()
+            .zip(packet().iter())
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum();
+        assert_eq!(flipped_bits, 1);
+    }
+
+    #[test]
+    fn drops_packets_when_queue_is_full() {
+        // 16 byte packets take a second each to send at 128 bits per second
+        let config = LinkConditionerConfig::default().with_bandwidth(
+            Some(128),
+            None,
+            QueueLimit::Packets(2),
+        );
+        let mut conditioner = LinkConditioner::new(&config, LinkDirection::Incoming);
+        for _ in 0..3 {
+            conditioner.process_packet(packet());
+        }
+
+        assert!(!conditioner.has_item());
+        assert_eq!(conditioner.time_queue.len(), 2);
+        let first_departure = conditioner.next_deadline().unwrap();
+        assert!(first_departure.until() > Duration::from_millis(900));
+    }
+
+    #[test]
+    fn latency_never_goes_negative() {
+        let config = LinkConditionerConfig::new(5, 500, 0.0);
+        let mut conditioner = LinkConditioner::new(&config, LinkDirection::Incoming);
+        for _ in 0..100 {
+            conditioner.process_packet(packet());
+        }
+        assert_eq!(conditioner.time_queue.len(), 100);
+    }
+
+    #[test]
+    fn gilbert_elliott_loses_packets_in_bursts() {
+        let config = LinkConditionerConfig::default().with_loss_model(LossModel::GilbertElliott {
+            good_to_bad: 1.0,
+            bad_to_good: 0.0,
+            bad_loss: 1.0,
+        });
+        let mut conditioner = LinkConditioner::new(&config, LinkDirection::Incoming);
+        for _ in 0..10 {
+            conditioner.process_packet(packet());
+        }
+        assert!(conditioner.bad_state);
+        assert!(conditioner.pop_item().is_none());
+    }
+
+    #[test]
+    fn handle_changes_config_and_reports_stats() {
+        let handle = LinkConditionerHandle::new(LinkConditionerConfig::default());
+        let mut conditioner = LinkConditioner::from_handle(&handle, LinkDirection::Incoming);
+        conditioner.process_packet(packet());
+        assert_eq!(handle.stats(LinkDirection::Incoming).queue_depth, 1);
+        assert!(conditioner.pop_item().is_some());
+
+        handle.update(|config| config.incoming_loss = 1.0);
+        conditioner.process_packet(packet());
+        assert!(conditioner.pop_item().is_none());
+
+        let stats = handle.stats(LinkDirection::Incoming);
+        assert_eq!(stats.packets_processed, 2);
+        assert_eq!(stats.packets_dropped, 1);
+        assert_eq!(stats.queue_depth, 0);
+        assert_eq!(handle.stats(LinkDirection::Outgoing).packets_processed, 0);
+    }
+
+    #[test]
+    fn forgets_queued_packets_when_dropped() {
+        let handle = LinkConditionerHandle::new(LinkConditionerConfig::new(1000, 0, 0.0));
+        let mut conditioner = LinkConditioner::from_handle(&handle, LinkDirection::Incoming);
+        for _ in 0..3 {
+            conditioner.process_packet(packet());
+        }
+        assert_eq!(handle.stats(LinkDirection::Incoming).queue_depth, 3);
+
+        let clone = conditioner.clone();
+        assert_eq!(handle.stats(LinkDirection::Incoming).queue_depth, 6);
+        drop(clone);
+        assert_eq!(handle.stats(LinkDirection::Incoming).queue_depth, 3);
+        drop(conditioner);
+        assert_eq!(handle.stats(LinkDirection::Incoming).queue_depth, 0);
+    }
+
+    #[test]
+    fn replays_traces() {
+        let trace = ConditionSchedule::from_trace(vec![Some(0), None, Some(0)]);
+        let config = LinkConditionerConfig::default().with_incoming_schedule(trace);
+        let mut conditioner = LinkConditioner::new(&config, LinkDirection::Incoming);
+        for byte in 0..4 {
+            conditioner.process_packet(vec![byte].into_boxed_slice());
+        }
+
+        let mut delivered = Vec::new();
+        while let Some(packet) = conditioner.pop_item() {
+            delivered.push(packet[0]);
+        }
+        delivered.sort();
+        // the second packet is lost, & the trace has ended by the fourth
+        assert_eq!(delivered, vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn simulates_time_with_a_manual_clock() {
+        let clock = ManualClock::new();
+        let schedule = ConditionSchedule::new().ramp_to(Duration::from_secs(20), 300, 0, 0.0);
+        let config = LinkConditionerConfig::new(100, 0, 0.0).with_incoming_schedule(schedule);
+        let mut conditioner = LinkConditioner::new(&config, LinkDirection::Incoming)
+            .with_clock(Arc::new(clock.clone()));
+
+        // 30 seconds of packets at 50 per second
+        let mut sent_at = Vec::new();
+        let mut delays = Vec::new();
+        for id in 0..1500u16 {
+            sent_at.push(clock.total_elapsed());
+            conditioner.process_packet(id.to_be_bytes().to_vec().into_boxed_slice());
+            clock.advance(Duration::from_millis(20));
+            while let Some(packet) = conditioner.pop_item() {
+                let id = u16::from_be_bytes([packet[0], packet[1]]);
+                delays.push(clock.total_elapsed() - sent_at[id as usize]);
+            }
+        }
+
+        // Packets sent less than 300ms before the end are still in flight
+        assert_eq!(delays.len(), 1500 - 14);
+        assert!(delays.first().unwrap() >= &Duration::from_millis(100));
+        assert!(delays.last().unwrap() >= &Duration::from_millis(300));
+        assert!(conditioner.next_deadline().is_some());
+    }
+
+    #[test]
+    fn same_seed_gives_same_conditions() {
+        let config = LinkConditionerConfig::new(0, 0, 0.5).with_seed(42);
+        let run = || {
+            let mut conditioner = LinkConditioner::new(&config, LinkDirection::Incoming);
+            for byte in 0..100 {
+                conditioner.process_packet(vec![byte].into_boxed_slice());
+            }
+            let mut delivered = Vec::new();
+            while let Some(packet) = conditioner.pop_item() {
+                delivered.push(packet[0]);
+            }
+            delivered
+        };
+
+        let first = run();
+        assert!(!first.is_empty() && first.len() < 100);
+        assert_eq!(first, run());
+    }
+
+    #[test]
+    fn reorders_packets() {
+        let clock = ManualClock::new();
+        let config = LinkConditionerConfig::new(50, 0, 0.0)
+            .with_incoming_effects(0.0, 0.0, 0.0, 0.5)
+            .with_seed(7);
+        let mut conditioner = LinkConditioner::new(&config, LinkDirection::Incoming)
+            .with_clock(Arc::new(clock.clone()));
+
+        let mut delivered = Vec::new();
+        for id in 0..100u8 {
+            conditioner.process_packet(vec![id].into_boxed_slice());
+            clock.advance(Duration::from_millis(10));
+            while let Some(packet) = conditioner.pop_item() {
+                delivered.push(packet[0]);
+            }
+        }
+        clock.advance(Duration::from_secs(2));
+        while let Some(packet) = conditioner.pop_item() {
+            delivered.push(packet[0]);
+        }
+
+        assert_eq!(delivered.len(), 100);
+        let inversions = delivered
+            .windows(2)
+            .filter(|pair| pair[0] > pair[1])
+            .count();
+        assert!(inversions > 10);
+
+        // Reordered packets are still delivered, once each
+        let mut sorted = delivered.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn only_conditions_its_own_direction() {
+        let config = LinkConditionerConfig::new(0, 0, 1.0);
+        let mut incoming = LinkConditioner::new(&config, LinkDirection::Incoming);
+        let mut outgoing = LinkConditioner::new(&config, LinkDirection::Outgoing);
+        incoming.process_packet(packet());
+        outgoing.process_packet(packet());
+
+        assert!(incoming.pop_item().is_none());
+        assert_eq!(outgoing.pop_item(), Some(packet()));
+    }
 }
@@ -1,3 +1,5 @@
+use super::condition_schedule::ConditionSchedule;
+
 /// Contains configuration required to initialize a LinkConditioner
 #[derive(Clone)]
 pub struct LinkConditionerConfig {
@@ -5,50 +7,340 @@ pub struct LinkConditionerConfig {
     pub incoming_latency: u32,
     /// The maximum additional random latency to delay received incoming
     /// messages in milliseconds. This may be added OR subtracted from the
-    /// latency determined in the `incoming_latency` property above
+    /// latency determined in the `incoming_latency` property above, as shaped
+    /// by `jitter_distribution`
     pub incoming_jitter: u32,
-    /// The % chance that an incoming packet will be dropped.
-    /// Represented as a value between 0 and 1
+    /// The % chance that an incoming packet will be dropped, while in the good
+    /// state of `loss_model`. Represented as a value between 0 and 1
     pub incoming_loss: f32,
+    /// The % chance that an incoming packet will be received twice, with the
+    /// duplicate delayed independently. Represented as a value between 0 and 1
+    pub incoming_duplicate: f32,
+    /// The % chance that a random byte of an incoming packet will be
+    /// overwritten. Represented as a value between 0 and 1
+    pub incoming_corruption: f32,
+    /// The % chance that a single random bit of an incoming packet will be
+    /// flipped. Represented as a value between 0 and 1
+    pub incoming_bit_flip: f32,
+    /// The % chance that an incoming packet will be held back until
+    /// `reorder_distance` later packets have overtaken it. Represented as a
+    /// value between 0 and 1
+    pub incoming_reorder: f32,
+    /// The maximum rate at which incoming packets are received, in bits per
+    /// second. Packets arriving faster than this wait in a queue bounded by
+    /// `queue_limit`. `None` means unlimited
+    pub incoming_bandwidth: Option<u32>,
+    /// A script of changes to the incoming latency, jitter & loss, or a trace
+    /// of per-packet delays to replay
+    pub incoming_schedule: Option<ConditionSchedule>,
+    /// Delay to send outgoing messages in milliseconds
+    pub outgoing_latency: u32,
+    /// The maximum additional random latency to delay sent outgoing messages
+    /// in milliseconds. This may be added OR subtracted from the latency
+    /// determined in the `outgoing_latency` property above, as shaped by
+    /// `jitter_distribution`
+    pub outgoing_jitter: u32,
+    /// The % chance that an outgoing packet will be dropped, while in the good
+    /// state of `loss_model`. Represented as a value between 0 and 1
+    pub outgoing_loss: f32,
+    /// The % chance that an outgoing packet will be sent twice, with the
+    /// duplicate delayed independently. Represented as a value between 0 and 1
+    pub outgoing_duplicate: f32,
+    /// The % chance that a random byte of an outgoing packet will be
+    /// overwritten. Represented as a value between 0 and 1
+    pub outgoing_corruption: f32,
+    /// The % chance that a single random bit of an outgoing packet will be
+    /// flipped. Represented as a value between 0 and 1
+    pub outgoing_bit_flip: f32,
+    /// The % chance that an outgoing packet will be held back until
+    /// `reorder_distance` later packets have overtaken it. Represented as a
+    /// value between 0 and 1
+    pub outgoing_reorder: f32,
+    /// The maximum rate at which outgoing packets are sent, in bits per
+    /// second. Packets sent faster than this wait in a queue bounded by
+    /// `queue_limit`. `None` means unlimited
+    pub outgoing_bandwidth: Option<u32>,
+    /// A script of changes to the outgoing latency, jitter & loss, or a trace
+    /// of per-packet delays to replay
+    pub outgoing_schedule: Option<ConditionSchedule>,
+    /// The size of the queue in front of a bandwidth limited link, applied to
+    /// each direction separately. Packets which don't fit are dropped
+    pub queue_limit: QueueLimit,
+    /// How many later packets overtake a reordered packet. If fewer packets
+    /// follow within a second, the reordered packet is delivered anyway
+    pub reorder_distance: u32,
+    /// How losses are distributed over time, in both directions
+    pub loss_model: LossModel,
+    /// The shape of the random jitter added to latency, in both directions
+    pub jitter_distribution: JitterDistribution,
+    /// Seed for the random choices made by the LinkConditioners, so that a run
+    /// can be replayed exactly. When None, a seed is picked at random & logged.
+    /// A Server mixes the seed with each Client's address, so Clients don't
+    /// all see the same conditions
+    pub seed: Option<u64>,
 }
 
 impl LinkConditionerConfig {
-    /// Creates a new LinkConditionerConfig
+    /// Creates a new LinkConditionerConfig, which only conditions incoming
+    /// packets
     pub fn new(incoming_latency: u32, incoming_jitter: u32, incoming_loss: f32) -> Self {
         LinkConditionerConfig {
             incoming_latency,
             incoming_jitter,
             incoming_loss,
+            ..Default::default()
         }
     }
 
+    /// Sets the conditions applied to outgoing packets
+    pub fn with_outgoing(
+        mut self,
+        outgoing_latency: u32,
+        outgoing_jitter: u32,
+        outgoing_loss: f32,
+    ) -> Self {
+        self.outgoing_latency = outgoing_latency;
+        self.outgoing_jitter = outgoing_jitter;
+        self.outgoing_loss = outgoing_loss;
+        self
+    }
+
+    /// Sets the chances that incoming packets are duplicated, corrupted,
+    /// bit-flipped & reordered
+    pub fn with_incoming_effects(
+        mut self,
+        duplicate: f32,
+        corruption: f32,
+        bit_flip: f32,
+        reorder: f32,
+    ) -> Self {
+        self.incoming_duplicate = duplicate;
+        self.incoming_corruption = corruption;
+        self.incoming_bit_flip = bit_flip;
+        self.incoming_reorder = reorder;
+        self
+    }
+
+    /// Sets the chances that outgoing packets are duplicated, corrupted,
+    /// bit-flipped & reordered
+    pub fn with_outgoing_effects(
+        mut self,
+        duplicate: f32,
+        corruption: f32,
+        bit_flip: f32,
+        reorder: f32,
+    ) -> Self {
+        self.outgoing_duplicate = duplicate;
+        self.outgoing_corruption = corruption;
+        self.outgoing_bit_flip = bit_flip;
+        self.outgoing_reorder = reorder;
+        self
+    }
+
+    /// Limits the bandwidth of each direction, in bits per second, with a
+    /// queue of the given size in front of each
+    pub fn with_bandwidth(
+        mut self,
+        incoming_bandwidth: Option<u32>,
+        outgoing_bandwidth: Option<u32>,
+        queue_limit: QueueLimit,
+    ) -> Self {
+        self.incoming_bandwidth = incoming_bandwidth;
+        self.outgoing_bandwidth = outgoing_bandwidth;
+        self.queue_limit = queue_limit;
+        self
+    }
+
+    /// Sets the schedule applied to incoming packets
+    pub fn with_incoming_schedule(mut self, schedule: ConditionSchedule) -> Self {
+        self.incoming_schedule = Some(schedule);
+        self
+    }
+
+    /// Sets the schedule applied to outgoing packets
+    pub fn with_outgoing_schedule(mut self, schedule: ConditionSchedule) -> Self {
+        self.outgoing_schedule = Some(schedule);
+        self
+    }
+
+    /// Sets how losses are distributed over time
+    pub fn with_loss_model(mut self, loss_model: LossModel) -> Self {
+        self.loss_model = loss_model;
+        self
+    }
+
+    /// Sets the shape of the random jitter added to latency
+    pub fn with_jitter_distribution(mut self, jitter_distribution: JitterDistribution) -> Self {
+        self.jitter_distribution = jitter_distribution;
+        self
+    }
+
+    /// Sets the seed for the random choices made by the LinkConditioners
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns whether any conditions are applied to outgoing packets
+    pub fn conditions_outgoing(&self) -> bool {
+        self.outgoing_latency > 0
+            || self.outgoing_jitter > 0
+            || self.outgoing_loss > 0.0
+            || self.outgoing_duplicate > 0.0
+            || self.outgoing_corruption > 0.0
+            || self.outgoing_bit_flip > 0.0
+            || self.outgoing_reorder > 0.0
+            || self.outgoing_bandwidth.is_some()
+            || self.outgoing_schedule.is_some()
+    }
+
     /// Creates a new LinkConditioner that simulates a connection which is in a
     /// good condition
     pub fn good_condition() -> Self {
-        LinkConditionerConfig {
-            incoming_latency: 50,
-            incoming_jitter: 10,
-            incoming_loss: 0.01,
-        }
+        LinkConditionerConfig::new(50, 10, 0.01)
     }
 
     /// Creates a new LinkConditioner that simulates a connection which is in an
     /// average condition
     pub fn average_condition() -> Self {
-        LinkConditionerConfig {
-            incoming_latency: 200,
-            incoming_jitter: 20,
-            incoming_loss: 0.055,
-        }
+        LinkConditionerConfig::new(200, 20, 0.055)
     }
 
     /// Creates a new LinkConditioner that simulates a connection which is in an
     /// poor condition
     pub fn poor_condition() -> Self {
+        LinkConditionerConfig::new(350, 30, 0.1)
+    }
+
+    /// Creates a new LinkConditioner that simulates a home Wi-Fi connection,
+    /// with occasional latency spikes & short bursts of loss
+    pub fn wifi_condition() -> Self {
+        LinkConditionerConfig::new(10, 8, 0.002)
+            .with_loss_model(LossModel::GilbertElliott {
+                good_to_bad: 0.01,
+                bad_to_good: 0.4,
+                bad_loss: 0.5,
+            })
+            .with_jitter_distribution(JitterDistribution::Pareto { shape: 2.5 })
+    }
+
+    /// Creates a new LinkConditioner that simulates a 4G LTE mobile connection
+    pub fn lte_condition() -> Self {
+        LinkConditionerConfig::new(45, 15, 0.005)
+            .with_loss_model(LossModel::GilbertElliott {
+                good_to_bad: 0.005,
+                bad_to_good: 0.2,
+                bad_loss: 0.3,
+            })
+            .with_jitter_distribution(JitterDistribution::Normal)
+    }
+
+    /// Creates a new LinkConditioner that simulates a 3G mobile connection,
+    /// with high latency, heavy-tailed jitter & long bursts of loss
+    pub fn mobile_3g_condition() -> Self {
+        LinkConditionerConfig::new(150, 40, 0.02)
+            .with_loss_model(LossModel::GilbertElliott {
+                good_to_bad: 0.02,
+                bad_to_good: 0.1,
+                bad_loss: 0.6,
+            })
+            .with_jitter_distribution(JitterDistribution::Pareto { shape: 2.0 })
+    }
+
+    /// Creates a new LinkConditioner that simulates a geostationary satellite
+    /// connection, with very high but steady latency
+    pub fn satellite_condition() -> Self {
+        LinkConditionerConfig::new(300, 15, 0.01)
+            .with_jitter_distribution(JitterDistribution::Normal)
+    }
+
+    /// Creates one of the named LinkConditionerConfig presets: "good",
+    /// "average", "poor", "wifi", "lte", "3g" or "satellite"
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "good" => Some(LinkConditionerConfig::good_condition()),
+            "average" => Some(LinkConditionerConfig::average_condition()),
+            "poor" => Some(LinkConditionerConfig::poor_condition()),
+            "wifi" => Some(LinkConditionerConfig::wifi_condition()),
+            "lte" | "4g" => Some(LinkConditionerConfig::lte_condition()),
+            "3g" => Some(LinkConditionerConfig::mobile_3g_condition()),
+            "satellite" => Some(LinkConditionerConfig::satellite_condition()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for LinkConditionerConfig {
+    fn default() -> Self {
         LinkConditionerConfig {
-            incoming_latency: 350,
-            incoming_jitter: 30,
-            incoming_loss: 0.1,
+            incoming_latency: 0,
+            incoming_jitter: 0,
+            incoming_loss: 0.0,
+            incoming_duplicate: 0.0,
+            incoming_corruption: 0.0,
+            incoming_bit_flip: 0.0,
+            incoming_reorder: 0.0,
+            incoming_bandwidth: None,
+            incoming_schedule: None,
+            outgoing_latency: 0,
+            outgoing_jitter: 0,
+            outgoing_loss: 0.0,
+            outgoing_duplicate: 0.0,
+            outgoing_corruption: 0.0,
+            outgoing_bit_flip: 0.0,
+            outgoing_reorder: 0.0,
+            outgoing_bandwidth: None,
+            outgoing_schedule: None,
+            queue_limit: QueueLimit::Unlimited,
+            reorder_distance: 1,
+            loss_model: LossModel::Uniform,
+            jitter_distribution: JitterDistribution::Uniform,
+            seed: None,
         }
     }
 }
+
+/// The size of the queue in front of a bandwidth limited link
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum QueueLimit {
+    /// The queue grows without bound, so packets are delayed but never dropped
+    Unlimited,
+    /// The queue holds at most this many bytes
+    Bytes(u32),
+    /// The queue holds at most this many packets
+    Packets(u32),
+}
+
+/// How losses are distributed over time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LossModel {
+    /// Each packet is lost independently, with the direction's loss chance
+    Uniform,
+    /// A two-state Gilbert-Elliott model, where losses come in bursts. In the
+    /// good state packets are lost with the direction's loss chance, in the
+    /// bad state with `bad_loss`. The state may change before each packet
+    GilbertElliott {
+        /// The % chance of moving from the good to the bad state
+        good_to_bad: f32,
+        /// The % chance of moving from the bad to the good state
+        bad_to_good: f32,
+        /// The % chance that a packet is lost in the bad state
+        bad_loss: f32,
+    },
+}
+
+/// The shape of the random jitter added to latency
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JitterDistribution {
+    /// Evenly distributed between plus & minus the jitter
+    Uniform,
+    /// Normally distributed, with the jitter as the standard deviation
+    Normal,
+    /// Only ever adds latency, mostly small amounts with rare large spikes.
+    /// The jitter is the scale of the distribution, & lower `shape` values
+    /// give a heavier tail
+    Pareto {
+        /// The shape parameter of the Pareto distribution, e.g. 2.0
+        shape: f32,
+    },
+}
@@ -0,0 +1,221 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+use log::info;
+
+use super::{link_condition_logic::LinkDirection, link_conditioner_config::LinkConditionerConfig};
+
+/// Used to change the conditions simulated by a Socket's LinkConditioners
+/// while it runs, & to read what they have done. Clones share the same config
+/// & statistics
+#[derive(Clone)]
+pub struct LinkConditionerHandle {
+    config: Arc<RwLock<LinkConditionerConfig>>,
+    generation: Arc<AtomicU64>,
+    incoming_stats: Arc<StatsCounters>,
+    outgoing_stats: Arc<StatsCounters>,
+}
+
+impl LinkConditionerHandle {
+    /// Creates a new LinkConditionerHandle with the given config
+    pub fn new(config: LinkConditionerConfig) -> Self {
+        log_seed(&config);
+        LinkConditionerHandle::without_logging(config)
+    }
+
+    // Used for the private handle of each LinkConditioner, whose seed is
+    // logged at debug level instead
+    pub(crate) fn without_logging(config: LinkConditionerConfig) -> Self {
+        LinkConditionerHandle {
+            config: Arc::new(RwLock::new(config)),
+            generation: Arc::new(AtomicU64::new(0)),
+            incoming_stats: Arc::new(StatsCounters::default()),
+            outgoing_stats: Arc::new(StatsCounters::default()),
+        }
+    }
+
+    /// Gets a copy of the current config
+    pub fn config(&self) -> LinkConditionerConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Replaces the config. Packets which are already queued keep the
+    /// timestamp they were given
+    pub fn set_config(&self, config: LinkConditionerConfig) {
+        let mut current = self.config.write().unwrap();
+        if config.seed != current.seed {
+            log_seed(&config);
+        }
+        *current = config;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Changes the config in place, e.g. `handle.update(|config|
+    /// config.incoming_latency = 300)`. No packet is processed with a
+    /// partially updated config
+    pub fn update(&self, update_fn: impl FnOnce(&mut LinkConditionerConfig)) {
+        let mut config = self.config.write().unwrap();
+        let seed = config.seed;
+        update_fn(&mut config);
+        if config.seed != seed {
+            log_seed(&config);
+        }
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Returns a number which increases each time the config changes
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Gets what the LinkConditioners for the given direction have done so far
+    pub fn stats(&self, direction: LinkDirection) -> LinkConditionerStats {
+        self.counters(direction).snapshot()
+    }
+
+    /// Resets the statistics of both directions, other than the queue depth
+    pub fn reset_stats(&self) {
+        self.incoming_stats.reset();
+        self.outgoing_stats.reset();
+    }
+
+    pub(crate) fn counters(&self, direction: LinkDirection) -> &Arc<StatsCounters> {
+        match direction {
+            LinkDirection::Incoming => &self.incoming_stats,
+            LinkDirection::Outgoing => &self.outgoing_stats,
+        }
+    }
+}
+
+// Logs the seed a config is applied with once, rather than for every
+// LinkConditioner it is given to, which log the seeds they derive from it at
+// debug level
+fn log_seed(config: &LinkConditionerConfig) {
+    match config.seed {
+        Some(seed) => info!("link conditioner: seed is {}", seed),
+        None => info!(
+            "link conditioner: unseeded, each conditioner logs its random seed at debug level"
+        ),
+    }
+}
+
+/// A snapshot of what the LinkConditioners for one direction have done
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinkConditionerStats {
+    /// Number of packets given to the conditioner
+    pub packets_processed: u64,
+    /// Number of packets delivered later than they would have been without
+    /// the conditioner
+    pub packets_delayed: u64,
+    /// Number of packets dropped, either by loss or by a full queue
+    pub packets_dropped: u64,
+    /// Number of extra copies of packets delivered
+    pub packets_duplicated: u64,
+    /// Number of packets which had a byte corrupted or a bit flipped
+    pub packets_corrupted: u64,
+    /// Number of packets held back to be overtaken by later packets
+    pub packets_reordered: u64,
+    /// The average delay added to delivered packets
+    pub average_delay: Duration,
+    /// Number of packets currently waiting to be delivered
+    pub queue_depth: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct StatsCounters {
+    processed: AtomicU64,
+    delayed: AtomicU64,
+    dropped: AtomicU64,
+    duplicated: AtomicU64,
+    corrupted: AtomicU64,
+    reordered: AtomicU64,
+    scheduled: AtomicU64,
+    total_delay_micros: AtomicU64,
+    queue_depth: AtomicU64,
+}
+
+impl StatsCounters {
+    pub fn processed(&self) {
+        self.processed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn duplicated(&self) {
+        self.duplicated.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn corrupted(&self) {
+        self.corrupted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn reordered(&self) {
+        self.reordered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn scheduled(&self, delay: Duration) {
+        self.scheduled.fetch_add(1, Ordering::Relaxed);
+        if delay > Duration::ZERO {
+            self.delayed.fetch_add(1, Ordering::Relaxed);
+        }
+        self.total_delay_micros
+            .fetch_add(delay.as_micros() as u64, Ordering::Relaxed);
+        self.queue_depth.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn delivered(&self) {
+        self.discarded(1);
+    }
+
+    pub fn queued(&self, count: u64) {
+        self.queue_depth.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn discarded(&self, count: u64) {
+        let _ = self
+            .queue_depth
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |depth| {
+                Some(depth.saturating_sub(count))
+            });
+    }
+
+    fn reset(&self) {
+        for counter in [
+            &self.processed,
+            &self.delayed,
+            &self.dropped,
+            &self.duplicated,
+            &self.corrupted,
+            &self.reordered,
+            &self.scheduled,
+            &self.total_delay_micros,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn snapshot(&self) -> LinkConditionerStats {
+        let scheduled = self.scheduled.load(Ordering::Relaxed);
+        let total_delay_micros = self.total_delay_micros.load(Ordering::Relaxed);
+        LinkConditionerStats {
+            packets_processed: self.processed.load(Ordering::Relaxed),
+            packets_delayed: self.delayed.load(Ordering::Relaxed),
+            packets_dropped: self.dropped.load(Ordering::Relaxed),
+            packets_duplicated: self.duplicated.load(Ordering::Relaxed),
+            packets_corrupted: self.corrupted.load(Ordering::Relaxed),
+            packets_reordered: self.reordered.load(Ordering::Relaxed),
+            average_delay: match scheduled {
+                0 => Duration::ZERO,
+                _ => Duration::from_micros(total_delay_micros / scheduled),
+            },
+            queue_depth: self.queue_depth.load(Ordering::Relaxed),
+        }
+    }
+}
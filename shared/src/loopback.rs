@@ -0,0 +1,181 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex, Weak},
+};
+
+// Loopback addresses are never bound, they only identify the Sockets on a
+// LoopbackHub
+const LOOPBACK_IP: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+const SERVER_PORT: u16 = 1;
+const FIRST_CLIENT_PORT: u16 = 49152;
+
+type Inbox = Arc<Mutex<VecDeque<Box<[u8]>>>>;
+type WeakInbox = Weak<Mutex<VecDeque<Box<[u8]>>>>;
+type ServerSink = Box<dyn FnMut(SocketAddr, Box<[u8]>) + Send>;
+
+/// An in-memory link between a Server Socket & any number of Client Sockets
+/// in the same process, used in place of UDP or WebRTC for tests & for
+/// servers which are also played on locally. Each Client gets a distinct fake
+/// address. Clones share the same link
+#[derive(Clone)]
+pub struct LoopbackHub {
+    inner: Arc<Mutex<HubState>>,
+}
+
+struct HubState {
+    server_sink: Option<ServerSink>,
+    clients: HashMap<SocketAddr, WeakInbox>,
+    next_port: u16,
+}
+
+impl LoopbackHub {
+    /// Creates a new LoopbackHub, with no Sockets attached
+    pub fn new() -> Self {
+        LoopbackHub {
+            inner: Arc::new(Mutex::new(HubState {
+                server_sink: None,
+                clients: HashMap::new(),
+                next_port: FIRST_CLIENT_PORT,
+            })),
+        }
+    }
+
+    /// Gets the fake address of the Server
+    pub fn server_addr(&self) -> SocketAddr {
+        SocketAddr::new(LOOPBACK_IP, SERVER_PORT)
+    }
+
+    /// Attaches a new Client to the hub, with the next free fake address
+    pub fn add_client(&self) -> LoopbackClient {
+        let mut state = self.inner.lock().unwrap();
+        state.clients.retain(|_, inbox| inbox.strong_count() > 0);
+        let address = loop {
+            let address = SocketAddr::new(LOOPBACK_IP, state.next_port);
+            state.next_port = state.next_port.checked_add(1).unwrap_or(FIRST_CLIENT_PORT);
+            if !state.clients.contains_key(&address) {
+                break address;
+            }
+        };
+        let inbox: Inbox = Arc::new(Mutex::new(VecDeque::new()));
+        state.clients.insert(address, Arc::downgrade(&inbox));
+        LoopbackClient {
+            address,
+            hub: self.clone(),
+            inbox,
+        }
+    }
+
+    /// Gets the addresses of the Clients currently attached to the hub
+    pub fn client_addrs(&self) -> Vec<SocketAddr> {
+        let state = self.inner.lock().unwrap();
+        state
+            .clients
+            .iter()
+            .filter(|(_, inbox)| inbox.strong_count() > 0)
+            .map(|(address, _)| *address)
+            .collect()
+    }
+
+    /// Attaches the Server to the hub. Packets from Clients are passed to the
+    /// given function as they are sent. Packets sent while no Server is
+    /// attached are dropped
+    pub fn attach_server(&self, sink: impl FnMut(SocketAddr, Box<[u8]>) + Send + 'static) {
+        self.inner.lock().unwrap().server_sink = Some(Box::new(sink));
+    }
+
+    /// Sends a packet from the Server to the Client with the given address.
+    /// Returns false if no such Client is attached
+    pub fn send_to_client(&self, address: &SocketAddr, payload: Box<[u8]>) -> bool {
+        let inbox = self
+            .inner
+            .lock()
+            .unwrap()
+            .clients
+            .get(address)
+            .and_then(Weak::upgrade);
+        match inbox {
+            Some(inbox) => {
+                inbox.lock().unwrap().push_back(payload);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn send_to_server(&self, address: SocketAddr, payload: Box<[u8]>) {
+        if let Some(sink) = &mut self.inner.lock().unwrap().server_sink {
+            sink(address, payload);
+        }
+    }
+}
+
+impl Default for LoopbackHub {
+    fn default() -> Self {
+        LoopbackHub::new()
+    }
+}
+
+/// A Client's end of a LoopbackHub. The Client is detached from the hub once
+/// every clone is dropped
+#[derive(Clone)]
+pub struct LoopbackClient {
+    address: SocketAddr,
+    hub: LoopbackHub,
+    inbox: Inbox,
+}
+
+impl LoopbackClient {
+    /// Gets the fake address of the Client
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Gets the fake address of the Server
+    pub fn server_addr(&self) -> SocketAddr {
+        self.hub.server_addr()
+    }
+
+    /// Sends a packet to the Server
+    pub fn send(&self, payload: &[u8]) {
+        self.hub.send_to_server(self.address, payload.into());
+    }
+
+    /// Receives the next packet from the Server, if any are waiting
+    pub fn receive(&self) -> Option<Box<[u8]>> {
+        self.inbox.lock().unwrap().pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::LoopbackHub;
+
+    #[test]
+    fn delivers_between_server_and_clients() {
+        let hub = LoopbackHub::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = received.clone();
+        hub.attach_server(move |address, payload| {
+            received_clone.lock().unwrap().push((address, payload))
+        });
+
+        let first = hub.add_client();
+        let second = hub.add_client();
+        assert_ne!(first.address(), second.address());
+
+        second.send(&[2]);
+        assert_eq!(received.lock().unwrap()[0].0, second.address());
+
+        assert!(hub.send_to_client(&first.address(), vec![1].into_boxed_slice()));
+        assert_eq!(first.receive().as_deref(), Some(&[1u8][..]));
+        assert_eq!(second.receive(), None);
+
+        let second_addr = second.address();
+        drop(second);
+        assert!(!hub.send_to_client(&second_addr, vec![2].into_boxed_slice()));
+        assert_eq!(hub.client_addrs(), vec![first.address()]);
+    }
+}
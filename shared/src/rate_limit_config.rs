@@ -0,0 +1,46 @@
+use std::{default::Default, time::Duration};
+
+/// Contains configuration for limiting how fast a single remote address may
+/// send packets to the Server Socket
+#[derive(Clone)]
+pub struct RateLimitConfig {
+    /// Sustained number of packets per second accepted from each address
+    pub packets_per_second: u32,
+    /// Sustained number of bytes per second accepted from each address
+    pub bytes_per_second: u32,
+    /// Maximum number of packets an address may send in a single burst
+    pub packet_burst: u32,
+    /// Maximum number of bytes an address may send in a single burst
+    pub byte_burst: u32,
+    /// Number of packets dropped from an address within `abuse_window` after
+    /// which the address is reported as abusive
+    pub abuse_threshold: u32,
+    /// The window of time over which `abuse_threshold` applies
+    pub abuse_window: Duration,
+    /// Maximum number of addresses limited at once. Addresses are forgotten
+    /// once they stop sending, but while this many are still limited, packets
+    /// from new addresses are dropped
+    pub max_addresses: usize,
+}
+
+impl RateLimitConfig {
+    /// Creates a new RateLimitConfig, allowing bursts of up to one second's
+    /// worth of traffic
+    pub fn new(packets_per_second: u32, bytes_per_second: u32) -> Self {
+        RateLimitConfig {
+            packets_per_second,
+            bytes_per_second,
+            packet_burst: packets_per_second,
+            byte_burst: bytes_per_second,
+            abuse_threshold: packets_per_second,
+            abuse_window: Duration::from_secs(5),
+            max_addresses: 16 * 1024,
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig::new(120, 120 * 1200)
+    }
+}
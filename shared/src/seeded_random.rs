@@ -0,0 +1,86 @@
+use crate::Random;
+
+/// A pseudo-random number generator which produces the same sequence on
+/// every platform for a given seed, so that simulated network conditions can
+/// be replayed exactly. Uses the SplitMix64 algorithm, which is fast but not
+/// suitable for cryptography
+#[derive(Clone, Debug)]
+pub struct SeededRandom {
+    seed: u64,
+    state: u64,
+}
+
+impl SeededRandom {
+    /// Creates a new SeededRandom which generates the sequence for the given
+    /// seed
+    pub fn new(seed: u64) -> Self {
+        SeededRandom { seed, state: seed }
+    }
+
+    /// Creates a new SeededRandom with a seed taken from the platform's own
+    /// random number generator. Use `seed()` to get the seed, so the sequence
+    /// can be replayed later
+    pub fn from_entropy() -> Self {
+        let high = Random::gen_range_u32(0, u32::MAX) as u64;
+        let low = Random::gen_range_u32(0, u32::MAX) as u64;
+        SeededRandom::new((high << 32) | low)
+    }
+
+    /// Gets the seed the sequence was created from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// returns the next random u64 value in the sequence
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut value = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        value ^ (value >> 31)
+    }
+
+    /// returns a random f32 value between an upper & lower bound
+    pub fn gen_range_f32(&mut self, lower: f32, upper: f32) -> f32 {
+        // The top 24 bits fill an f32's mantissa exactly, giving [0, 1)
+        let unit = (self.next_u64() >> 40) as f32 / (1u32 << 24) as f32;
+        lower + unit * (upper - lower)
+    }
+
+    /// returns a random u32 value between an upper & lower bound
+    pub fn gen_range_u32(&mut self, lower: u32, upper: u32) -> u32 {
+        let range = upper.saturating_sub(lower) as u64;
+        lower + (((self.next_u64() >> 32) * range) >> 32) as u32
+    }
+
+    /// returns a random boolean value
+    pub fn gen_bool(&mut self) -> bool {
+        self.next_u64() >> 63 == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SeededRandom;
+
+    #[test]
+    fn same_seed_gives_same_sequence() {
+        let mut first = SeededRandom::new(1234);
+        let mut second = SeededRandom::new(1234);
+        for _ in 0..100 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+
+        // Values from the reference SplitMix64 implementation
+        let mut random = SeededRandom::new(0);
+        assert_eq!(random.next_u64(), 0xE220_A839_7B1D_CDAF);
+        assert_eq!(random.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+
+        for _ in 0..1000 {
+            let value = random.gen_range_u32(10, 20);
+            assert!((10..20).contains(&value));
+            let value = random.gen_range_f32(-1.0, 1.0);
+            assert!((-1.0..1.0).contains(&value));
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::{default::Default, time::Duration};
+
+/// Contains limits applied by the Server Socket's signaling listener, to
+/// protect it from slow or abusive clients
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignalingConfig {
+    /// Maximum amount of time a client may take to send a complete request,
+    /// after which it receives a 408 response
+    pub request_timeout: Duration,
+    /// Maximum size in bytes of a request line & headers, beyond which a
+    /// client receives a 431 response
+    pub max_header_size: usize,
+    /// Maximum size in bytes of a request body, beyond which a client receives
+    /// a 413 response
+    pub max_body_size: usize,
+    /// Maximum number of signaling connections being served at once, beyond
+    /// which new connections receive a 503 response
+    pub max_connections: usize,
+    /// Maximum number of WebRTC session requests accepted from a single IP
+    /// address within `session_rate_window`, beyond which a client receives a
+    /// 429 response. None by default, as many clients can share an IP address
+    /// behind a NAT
+    pub max_session_requests_per_ip: Option<u32>,
+    /// The window of time over which `max_session_requests_per_ip` applies
+    pub session_rate_window: Duration,
+}
+
+impl Default for SignalingConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(10),
+            max_header_size: 8 * 1024,
+            max_body_size: 64 * 1024,
+            max_connections: 256,
+            max_session_requests_per_ip: None,
+            session_rate_window: Duration::from_secs(60),
+        }
+    }
+}
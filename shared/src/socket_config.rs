@@ -1,6 +1,11 @@
 use std::default::Default;
 
-use super::link_conditioner_config::LinkConditionerConfig;
+use super::{
+    connection_quality_config::ConnectionQualityConfig, ip_filter::IpFilter,
+    keepalive_config::KeepaliveConfig, link_conditioner_config::LinkConditionerConfig,
+    rate_limit_config::RateLimitConfig, signaling_config::SignalingConfig,
+    time_sync_config::TimeSyncConfig,
+};
 
 const DEFAULT_RTC_PATH: &str = "rtc_session";
 
@@ -11,6 +16,28 @@ pub struct SocketConfig {
     pub link_condition: Option<LinkConditionerConfig>,
     /// The endpoint URL path to use for initiating new WebRTC sessions
     pub rtc_endpoint_path: String,
+    /// Limits applied by the Server Socket's signaling listener. When several
+    /// Sockets share a listener, the limits of the first one apply, & a
+    /// warning is logged for each later Socket with different limits
+    pub signaling: SignalingConfig,
+    /// Limits on how fast each remote address may send packets to the Server
+    /// Socket. Packets over the limit are dropped
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Allow & deny lists of remote addresses, enforced by the Server Socket
+    /// before WebRTC sessions are established and before packets are received.
+    /// Keep a clone of it to update the lists at runtime
+    pub ip_filter: IpFilter,
+    /// Lets the Client estimate the Server's clock, by exchanging probes
+    /// which are invisible to the application. Must be set on both sides
+    pub time_sync: Option<TimeSyncConfig>,
+    /// Lets both sides measure round trip time, jitter & packet loss, by
+    /// exchanging pings which are invisible to the application. Must be set
+    /// on both sides
+    pub connection_quality: Option<ConnectionQualityConfig>,
+    /// Lets both sides detect when the other has gone away, by exchanging
+    /// heartbeats which are invisible to the application, & saying goodbye
+    /// on disconnect. Must be set on both sides
+    pub keepalive: Option<KeepaliveConfig>,
 }
 
 impl SocketConfig {
@@ -30,6 +57,12 @@ impl SocketConfig {
         SocketConfig {
             link_condition,
             rtc_endpoint_path: endpoint_path,
+            signaling: SignalingConfig::default(),
+            rate_limit: None,
+            ip_filter: IpFilter::new(),
+            time_sync: None,
+            connection_quality: None,
+            keepalive: None,
         }
     }
 }
@@ -39,6 +72,12 @@ impl Default for SocketConfig {
         Self {
             link_condition: None,
             rtc_endpoint_path: DEFAULT_RTC_PATH.to_string(),
+            signaling: SignalingConfig::default(),
+            rate_limit: None,
+            ip_filter: IpFilter::new(),
+            time_sync: None,
+            connection_quality: None,
+            keepalive: None,
         }
     }
 }
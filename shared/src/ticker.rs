@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use super::{
+    clock::{real_clock, SharedClock},
+    Instant,
+};
+
+/// Schedules fixed timestep ticks. Each poll returns the number of ticks owed
+/// since the last, carrying over any time left towards the next tick so that
+/// ticks don't drift
+pub struct Ticker {
+    tick_duration: Duration,
+    max_catch_up: Option<u32>,
+    last_tick: Instant,
+    interpolation: f32,
+    ticks: u64,
+    clock: SharedClock,
+}
+
+impl Ticker {
+    /// Creates a new Ticker which ticks once every given Duration, starting
+    /// from now
+    pub fn new(tick_duration: Duration) -> Self {
+        Ticker::with_clock(tick_duration, real_clock())
+    }
+
+    /// Creates a new Ticker which reads the time from the given Clock
+    pub fn with_clock(tick_duration: Duration, clock: SharedClock) -> Self {
+        assert!(
+            tick_duration > Duration::ZERO,
+            "Ticker requires a tick duration greater than zero"
+        );
+        Ticker {
+            tick_duration,
+            max_catch_up: None,
+            last_tick: clock.now(),
+            interpolation: 0.0,
+            ticks: 0,
+            clock,
+        }
+    }
+
+    /// Limits the number of ticks a single poll may return. When more are
+    /// owed, e.g. after the process has been suspended, the extra ticks are
+    /// skipped rather than run in a burst
+    pub fn with_max_catch_up(mut self, max_ticks: u32) -> Self {
+        self.max_catch_up = Some(max_ticks);
+        self
+    }
+
+    /// Returns the number of ticks owed since the last poll
+    pub fn poll(&mut self) -> u32 {
+        let elapsed = self.clock.elapsed(&self.last_tick);
+        let owed = elapsed.as_nanos() / self.tick_duration.as_nanos();
+        let owed = u32::try_from(owed).unwrap_or(u32::MAX);
+
+        self.last_tick += self.tick_duration * owed;
+        let remainder = elapsed.saturating_sub(self.tick_duration * owed);
+        self.interpolation = remainder.as_secs_f32() / self.tick_duration.as_secs_f32();
+
+        let ticks = match self.max_catch_up {
+            Some(max_ticks) => owed.min(max_ticks),
+            None => owed,
+        };
+        self.ticks += u64::from(ticks);
+        ticks
+    }
+
+    /// Returns how far the time is towards the next tick as of the last poll,
+    /// between 0 & 1. Used to interpolate between the states of the last two
+    /// ticks when rendering
+    pub fn interpolation(&self) -> f32 {
+        self.interpolation
+    }
+
+    /// Returns the number of ticks returned by every poll so far
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Gets the Duration between ticks
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    /// Returns the time until the next tick is owed
+    pub fn until_next_tick(&self) -> Duration {
+        self.tick_duration
+            .saturating_sub(self.clock.elapsed(&self.last_tick))
+    }
+
+    /// Discards any time owed, so the next tick is a full tick duration from
+    /// now
+    pub fn reset(&mut self) {
+        self.last_tick = self.clock.now();
+        self.interpolation = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::Ticker;
+    use crate::ManualClock;
+
+    #[test]
+    fn carries_remainder_and_limits_catch_up() {
+        let clock = ManualClock::new();
+        let mut ticker = Ticker::with_clock(Duration::from_millis(50), Arc::new(clock.clone()))
+            .with_max_catch_up(4);
+
+        clock.advance(Duration::from_millis(120));
+        assert_eq!(ticker.poll(), 2);
+        assert!((ticker.interpolation() - 0.4).abs() < 0.001);
+
+        // The 20ms left over counts towards the next tick
+        clock.advance(Duration::from_millis(30));
+        assert_eq!(ticker.poll(), 1);
+        assert_eq!(ticker.interpolation(), 0.0);
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(ticker.poll(), 4);
+        assert_eq!(ticker.poll(), 0);
+        assert_eq!(ticker.ticks(), 7);
+    }
+}
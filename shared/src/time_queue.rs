@@ -1,73 +1,196 @@
 use std::{cmp::Ordering, collections::BinaryHeap};
 
-use super::Instant;
+use super::{
+    clock::{real_clock, SharedClock},
+    Instant,
+};
 
 /// A queue for items marked by time, will only ever pop items from the queue if
-/// the time
+/// the time has come. Items marked with the same time are popped in the order
+/// they were added
 #[derive(Clone)]
-pub struct TimeQueue<T: Eq + PartialEq> {
+pub struct TimeQueue<T> {
     queue: BinaryHeap<ItemContainer<T>>,
+    next_sequence: u64,
+    clock: SharedClock,
 }
 
-impl<T: Eq + PartialEq> TimeQueue<T> {
+impl<T> TimeQueue<T> {
     /// Create a new TimeQueue
     pub fn new() -> Self {
+        TimeQueue::with_clock(real_clock())
+    }
+
+    /// Create a new TimeQueue, which reads the time from the given Clock
+    pub fn with_clock(clock: SharedClock) -> Self {
         TimeQueue {
             queue: BinaryHeap::new(),
+            next_sequence: 0,
+            clock,
         }
     }
 
+    /// Changes the Clock the time is read from
+    pub fn set_clock(&mut self, clock: SharedClock) {
+        self.clock = clock;
+    }
+
     /// Adds an item to the queue marked by time
     pub fn add_item(&mut self, instant: Instant, item: T) {
-        self.queue.push(ItemContainer { instant, item });
+        self.queue.push(ItemContainer {
+            instant,
+            sequence: self.next_sequence,
+            item,
+        });
+        self.next_sequence += 1;
     }
 
     /// Returns whether or not there is an item that is ready to be returned
     pub fn has_item(&self) -> bool {
-        if self.queue.len() == 0 {
-            return false;
-        }
-        if let Some(item) = self.queue.peek() {
-            return item.instant <= Instant::now();
-        }
-        return false;
+        self.is_ready(&self.clock.now())
     }
 
     /// Pops an item from the queue if the sufficient time has elapsed
     pub fn pop_item(&mut self) -> Option<T> {
         if self.has_item() {
-            if let Some(container) = self.queue.pop() {
-                return Some(container.item);
-            }
+            return self.queue.pop().map(|container| container.item);
         }
-        return None;
+        None
+    }
+
+    /// Pops every item which is ready, in order. The time is read once, so
+    /// items which become ready while draining are left for later
+    pub fn drain_ready(&mut self) -> DrainReady<'_, T> {
+        DrainReady {
+            now: self.clock.now(),
+            queue: self,
+        }
+    }
+
+    /// Returns the Instant at which the next item will be ready, if any are
+    /// queued
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.queue.peek().map(|container| container.instant.clone())
     }
 
     /// Peeks at the top level item container on the queue
     pub fn peek_entry(&self) -> Option<&ItemContainer<T>> {
-        return self.queue.peek();
+        self.queue.peek()
+    }
+
+    /// Removes every item from the queue
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Keeps only the items for which the given function returns true
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        self.queue.retain(|container| keep(&container.item));
     }
 
     /// Returns the length of the underlying queue
     pub fn len(&self) -> usize {
-        return self.queue.len();
+        self.queue.len()
+    }
+
+    /// Returns whether the queue holds no items, ready or not
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    fn is_ready(&self, now: &Instant) -> bool {
+        match self.queue.peek() {
+            Some(container) => container.instant <= *now,
+            None => false,
+        }
     }
 }
 
-#[derive(Clone, Eq, PartialEq)]
-pub struct ItemContainer<T: Eq + PartialEq> {
+impl<T> Default for TimeQueue<T> {
+    fn default() -> Self {
+        TimeQueue::new()
+    }
+}
+
+/// An iterator over the items of a TimeQueue which are ready, returned by
+/// `TimeQueue::drain_ready()`
+pub struct DrainReady<'a, T> {
+    queue: &'a mut TimeQueue<T>,
+    now: Instant,
+}
+
+impl<T> Iterator for DrainReady<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.queue.is_ready(&self.now) {
+            return self.queue.queue.pop().map(|container| container.item);
+        }
+        None
+    }
+}
+
+#[derive(Clone)]
+pub struct ItemContainer<T> {
     pub instant: Instant,
+    sequence: u64,
     pub item: T,
 }
 
-impl<T: Eq + PartialEq> Ord for ItemContainer<T> {
+// The heap pops its greatest item, so the earliest instant, & then the
+// earliest added, compare greatest
+impl<T> Ord for ItemContainer<T> {
     fn cmp(&self, other: &ItemContainer<T>) -> Ordering {
-        other.instant.cmp(&self.instant)
+        other
+            .instant
+            .cmp(&self.instant)
+            .then_with(|| other.sequence.cmp(&self.sequence))
     }
 }
 
-impl<T: Eq + PartialEq> PartialOrd for ItemContainer<T> {
+impl<T> PartialOrd for ItemContainer<T> {
     fn partial_cmp(&self, other: &ItemContainer<T>) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
+
+impl<T> PartialEq for ItemContainer<T> {
+    fn eq(&self, other: &ItemContainer<T>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for ItemContainer<T> {}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::TimeQueue;
+    use crate::{Clock, ManualClock};
+
+    #[test]
+    fn drains_in_time_then_insertion_order() {
+        let clock = ManualClock::new();
+        let mut queue = TimeQueue::with_clock(Arc::new(clock.clone()));
+        let mut later = clock.now();
+        later.add_millis(100);
+        queue.add_item(later.clone(), "late");
+        for item in ["first", "second", "third"] {
+            queue.add_item(clock.now(), item);
+        }
+
+        assert_eq!(queue.next_deadline(), Some(clock.now()));
+        queue.retain(|item| *item != "second");
+        assert_eq!(
+            queue.drain_ready().collect::<Vec<_>>(),
+            vec!["first", "third"]
+        );
+        assert_eq!(queue.pop_item(), None);
+        assert_eq!(queue.next_deadline(), Some(later));
+
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(queue.pop_item(), Some("late"));
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,359 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::{
+    clock::{real_clock, SharedClock},
+    time_sync_config::TimeSyncConfig,
+    timestamp::Timestamp,
+    Instant,
+};
+
+// The estimated drift between the clocks is kept within this many parts per
+// million, as real clocks drift far less. Until drift has been measured, the
+// uncertainty allows for this much
+const MAX_DRIFT_PPM: f64 = 1000.0;
+// Drift is fitted to the samples of this long a window, up to a number of them
+const DRIFT_WINDOW: Duration = Duration::from_secs(300);
+const MAX_DRIFT_SAMPLES: usize = 512;
+// Samples spanning less than this say too little about drift, as the jitter of
+// their round trips outweighs it
+const MIN_DRIFT_SPAN: Duration = Duration::from_secs(30);
+const MIN_DRIFT_SAMPLES: usize = 8;
+// How many standard errors of the drift fit the uncertainty allows for
+const DRIFT_ERROR_MARGIN: f64 = 3.0;
+// Timestamps are whole milliseconds, so each reading may be off by up to one
+const TIMESTAMP_RESOLUTION: Duration = Duration::from_millis(2);
+
+/// Estimates the Server's clock on the Client, from the replies to timestamped
+/// probes. Clones share the same estimate
+#[derive(Clone)]
+pub struct TimeSync {
+    config: TimeSyncConfig,
+    state: Arc<Mutex<TimeSyncState>>,
+    clock: SharedClock,
+}
+
+struct TimeSyncState {
+    samples: VecDeque<TimeSample>,
+    // The samples of the drift window, oldest first
+    drift_samples: VecDeque<TimeSample>,
+    // Server clock seconds per Client clock second, less one
+    drift: f64,
+    // How far the drift may be from the real one, in the same units
+    drift_error: f64,
+}
+
+#[derive(Clone)]
+struct TimeSample {
+    // When the reply arrived at the Client
+    received_at: Instant,
+    // The Server's clock at that moment, in milliseconds since the Unix epoch
+    server_millis: f64,
+    rtt: Duration,
+}
+
+impl TimeSync {
+    /// Creates a new TimeSync, with no estimate until the first sample
+    pub fn new(config: TimeSyncConfig) -> Self {
+        TimeSync {
+            config,
+            state: Arc::new(Mutex::new(TimeSyncState {
+                samples: VecDeque::new(),
+                drift_samples: VecDeque::new(),
+                drift: 0.0,
+                drift_error: MAX_DRIFT_PPM / 1_000_000.0,
+            })),
+            clock: real_clock(),
+        }
+    }
+
+    /// Reads the time from the given Clock, rather than the wall clock. Used
+    /// with a ManualClock to simulate probes without waiting for them
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Gets the Clock the TimeSync reads the time from
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
+    /// Gets the config the TimeSync was created with
+    pub fn config(&self) -> &TimeSyncConfig {
+        &self.config
+    }
+
+    /// Records the reply to a probe which was sent at `sent_at` & whose reply
+    /// arrived at `received_at`, given the Server's clock when it received the
+    /// probe & when it sent the reply
+    pub fn add_sample(
+        &self,
+        sent_at: &Instant,
+        received_at: &Instant,
+        server_received: Timestamp,
+        server_sent: Timestamp,
+    ) {
+        // The time the Server spent holding the probe isn't part of the trip
+        let rtt = received_at
+            .duration_since(sent_at)
+            .saturating_sub(server_sent - server_received);
+        let sample = TimeSample {
+            received_at: received_at.clone(),
+            server_millis: server_sent.as_millis() as f64 + millis(rtt) / 2.0,
+            rtt,
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.update_drift(sample.clone());
+        state.samples.push_back(sample);
+        while state.samples.len() > self.config.sample_count.max(1) {
+            state.samples.pop_front();
+        }
+    }
+
+    /// Returns the estimated time on the Server's clock right now, or None if
+    /// no probe has been answered yet
+    pub fn estimated_server_time(&self) -> Option<Timestamp> {
+        self.server_time_at(&self.clock.now())
+    }
+
+    /// Returns the estimated time on the Server's clock at the given Instant,
+    /// or None if no probe has been answered yet
+    pub fn server_time_at(&self, instant: &Instant) -> Option<Timestamp> {
+        let state = self.state.lock().unwrap();
+        let best = state.best_sample()?;
+        let elapsed = signed_millis(instant, &best.received_at);
+        let server_millis = best.server_millis + elapsed * (1.0 + state.drift);
+        Some(Timestamp::from_millis(server_millis.max(0.0).round() as u64))
+    }
+
+    /// Returns how far the estimated Server time may be from the real one, or
+    /// None if no probe has been answered yet. This is half the round trip of
+    /// the probe the estimate is based on, as the trip may have been spent
+    /// entirely on the way out or back, plus the error the drift may have built
+    /// up since
+    pub fn uncertainty(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        let best = state.best_sample()?;
+        let age = self.clock.elapsed(&best.received_at);
+        Some(best.rtt / 2 + TIMESTAMP_RESOLUTION + age.mul_f64(state.drift_error))
+    }
+
+    /// Returns the shortest round trip time of the recent probes, or None if
+    /// no probe has been answered yet
+    pub fn round_trip_time(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        state.best_sample().map(|sample| sample.rtt)
+    }
+
+    /// Returns how much faster the Server's clock runs than the Client's, in
+    /// parts per million. This is zero until the probes span long enough to
+    /// measure it
+    pub fn drift_ppm(&self) -> f64 {
+        self.state.lock().unwrap().drift * 1_000_000.0
+    }
+}
+
+impl TimeSyncState {
+    // The sample with the shortest round trip has the least room for error
+    fn best_sample(&self) -> Option<&TimeSample> {
+        self.samples.iter().min_by_key(|sample| sample.rtt)
+    }
+
+    // Fits a line to the offset between the clocks over the drift window, by
+    // least squares weighted towards the samples with the shortest round trips,
+    // whose offsets are the most precise. The slope of the line is the drift
+    fn update_drift(&mut self, sample: TimeSample) {
+        self.drift_samples.push_back(sample);
+        let newest = self.drift_samples.back().unwrap().received_at.clone();
+        while self.drift_samples.len() > MAX_DRIFT_SAMPLES
+            || newest.duration_since(&self.drift_samples[0].received_at) > DRIFT_WINDOW
+        {
+            self.drift_samples.pop_front();
+        }
+
+        let max_drift = MAX_DRIFT_PPM / 1_000_000.0;
+        let first = &self.drift_samples[0];
+        let span = newest.duration_since(&first.received_at);
+        if span < MIN_DRIFT_SPAN || self.drift_samples.len() < MIN_DRIFT_SAMPLES {
+            self.drift = 0.0;
+            self.drift_error = max_drift;
+            return;
+        }
+
+        // Milliseconds on the Client's clock, & how far the Server's clock has
+        // moved beyond them, since the first sample
+        let points: Vec<(f64, f64, f64)> = self
+            .drift_samples
+            .iter()
+            .map(|sample| {
+                let x = millis(sample.received_at.duration_since(&first.received_at));
+                let y = sample.server_millis - first.server_millis - x;
+                let weight = 1.0 / millis(sample.rtt).max(1.0).powi(2);
+                (x, y, weight)
+            })
+            .collect();
+        let total_weight: f64 = points.iter().map(|(_, _, w)| w).sum();
+        let mean_x = points.iter().map(|(x, _, w)| x * w).sum::<f64>() / total_weight;
+        let mean_y = points.iter().map(|(_, y, w)| y * w).sum::<f64>() / total_weight;
+        let sxx: f64 = points
+            .iter()
+            .map(|(x, _, w)| w * (x - mean_x).powi(2))
+            .sum();
+        let sxy: f64 = points
+            .iter()
+            .map(|(x, y, w)| w * (x - mean_x) * (y - mean_y))
+            .sum();
+        let slope = sxy / sxx;
+        let residuals: f64 = points
+            .iter()
+            .map(|(x, y, w)| w * (y - mean_y - slope * (x - mean_x)).powi(2))
+            .sum();
+        let standard_error = (residuals / (points.len() - 2) as f64 / sxx).sqrt();
+
+        self.drift = slope.clamp(-max_drift, max_drift);
+        self.drift_error = (standard_error * DRIFT_ERROR_MARGIN).min(max_drift);
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+// Milliseconds from `from` to `to`, negative if `to` is earlier
+fn signed_millis(to: &Instant, from: &Instant) -> f64 {
+    match to.checked_duration_since(from) {
+        Some(duration) => millis(duration),
+        None => -millis(from.duration_since(to)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::TimeSync;
+    use crate::{
+        Clock, ControlPacket, Instant, ManualClock, SeededRandom, TimeSyncConfig, Timestamp,
+    };
+
+    #[test]
+    fn estimates_server_time_from_fastest_probe() {
+        let time_sync = TimeSync::new(TimeSyncConfig::default());
+        let start = Instant::now();
+        let at = |millis: u64| start.clone() + Duration::from_millis(millis);
+        // The Server's clock reads 1,000,000ms when the Client's reads `start`
+        let server = |millis: u64| Timestamp::from_millis(1_000_000 + millis);
+
+        assert_eq!(time_sync.server_time_at(&start), None);
+
+        // A 40ms round trip, with 25ms out & 15ms back, & a 200ms round trip
+        time_sync.add_sample(&at(0), &at(40), server(25), server(25));
+        time_sync.add_sample(&at(100), &at(300), server(150), server(150));
+
+        assert_eq!(time_sync.round_trip_time(), Some(Duration::from_millis(40)));
+        let estimate = time_sync.server_time_at(&at(1000)).unwrap();
+        // Off by half the difference between the two legs
+        assert_eq!(estimate, server(1005));
+        assert!(time_sync.uncertainty().unwrap() >= Duration::from_millis(20));
+
+        let reply = ControlPacket::TimeReply {
+            id: 7,
+            received: server(25),
+            sent: server(26),
+        };
+        assert_eq!(
+            ControlPacket::from_bytes(&reply.to_bytes()),
+            Some(reply.clone())
+        );
+        assert_eq!(ControlPacket::from_bytes(&[1, 2, 3]), None);
+
+        // Requests are padded to the size of their replies, & refused if not
+        let probe = ControlPacket::TimeProbe { id: 7 }.to_bytes();
+        let ping = ControlPacket::Ping {
+            id: 7,
+            report: None,
+        }
+        .to_bytes();
+        let pong = ControlPacket::Pong {
+            id: 7,
+            pings_received: 1,
+        }
+        .to_bytes();
+        assert_eq!(probe.len(), reply.to_bytes().len());
+        assert_eq!(ping.len(), pong.len());
+        assert_eq!(
+            ControlPacket::from_bytes(&ping),
+            Some(ControlPacket::Ping {
+                id: 7,
+                report: None
+            })
+        );
+        assert_eq!(ControlPacket::from_bytes(&probe[..9]), None);
+        assert_eq!(ControlPacket::from_bytes(&ping[..9]), None);
+    }
+
+    #[test]
+    fn converges_on_drift_despite_jitter() {
+        const DRIFT_PPM: f64 = 200.0;
+        let clock = Arc::new(ManualClock::new());
+        let time_sync = TimeSync::new(TimeSyncConfig::default()).with_clock(clock.clone());
+        let mut random = SeededRandom::new(45);
+        // The Server's clock reads 1,000,000,000ms when the Client's starts,
+        // & runs faster by DRIFT_PPM
+        let server_millis = || {
+            let elapsed = clock.total_elapsed().as_secs_f64() * 1000.0;
+            1_000_000_000.0 + elapsed * (1.0 + DRIFT_PPM / 1_000_000.0)
+        };
+        let server_now = || Timestamp::from_millis(server_millis().round() as u64);
+        // How far the estimate is from the Server's clock, within its
+        // uncertainty
+        let check_estimate = || {
+            let estimate = time_sync.estimated_server_time().unwrap().as_millis() as f64;
+            let error = (estimate - server_millis()).abs();
+            let uncertainty = time_sync.uncertainty().unwrap().as_secs_f64() * 1000.0;
+            assert!(
+                error <= uncertainty,
+                "{}ms from the Server's clock, beyond the {}ms uncertainty",
+                error,
+                uncertainty
+            );
+            uncertainty
+        };
+        let mut drift_error_after_one_minute = 0.0;
+
+        // Probes every second, whose trips out & back are jittered, & slower
+        // back than out
+        for second in 1..=600 {
+            let sent_at = clock.now();
+            let out = Duration::from_millis(10 + u64::from(random.gen_range_u32(0, 60)));
+            let held = Duration::from_millis(u64::from(random.gen_range_u32(0, 3)));
+            let back = Duration::from_millis(25 + u64::from(random.gen_range_u32(0, 60)));
+            clock.advance(out);
+            let server_received = server_now();
+            clock.advance(held);
+            let server_sent = server_now();
+            clock.advance(back);
+            time_sync.add_sample(&sent_at, &clock.now(), server_received, server_sent);
+
+            clock.advance(Duration::from_secs(1) - out - held - back);
+            check_estimate();
+            if second == 60 {
+                drift_error_after_one_minute = (time_sync.drift_ppm() - DRIFT_PPM).abs();
+            }
+        }
+
+        let drift_error = (time_sync.drift_ppm() - DRIFT_PPM).abs();
+        assert!(drift_error < 20.0, "drift is off by {}ppm", drift_error);
+        assert!(drift_error < drift_error_after_one_minute);
+
+        // The estimate holds without probes, as the uncertainty grows
+        let uncertainty = check_estimate();
+        clock.advance(Duration::from_secs(120));
+        assert!(check_estimate() > uncertainty);
+    }
+}
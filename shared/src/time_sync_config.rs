@@ -0,0 +1,28 @@
+use std::{default::Default, time::Duration};
+
+/// Contains configuration for estimating the Server's clock on the Client.
+/// Must be set on both the Client & the Server Socket
+#[derive(Clone)]
+pub struct TimeSyncConfig {
+    /// How often the Client sends a probe to the Server
+    pub probe_interval: Duration,
+    /// Number of recent probes the estimate is chosen from. The probe with the
+    /// shortest round trip gives the most accurate estimate
+    pub sample_count: usize,
+}
+
+impl TimeSyncConfig {
+    /// Creates a new TimeSyncConfig, probing at the given interval
+    pub fn new(probe_interval: Duration) -> Self {
+        TimeSyncConfig {
+            probe_interval,
+            sample_count: 8,
+        }
+    }
+}
+
+impl Default for TimeSyncConfig {
+    fn default() -> Self {
+        TimeSyncConfig::new(Duration::from_secs(1))
+    }
+}
@@ -1,36 +1,53 @@
-use std::time::{Duration, Instant};
+use std::time::Duration;
+
+use super::{
+    clock::{real_clock, SharedClock},
+    Instant,
+};
 
 /// A Timer with a given duration after which it will enter into a "Ringing"
 /// state. The Timer can be reset at an given time, or manually set to start
 /// "Ringing" again.
 pub struct Timer {
     duration: Duration,
-    last: Instant,
+    // None when the Timer has been made to ring manually
+    last: Option<Instant>,
+    clock: SharedClock,
 }
 
 impl Timer {
     /// Creates a new Timer with a given Duration
     pub fn new(duration: Duration) -> Self {
+        Timer::with_clock(duration, real_clock())
+    }
+
+    /// Creates a new Timer with a given Duration, which reads the time from
+    /// the given Clock
+    pub fn with_clock(duration: Duration, clock: SharedClock) -> Self {
         Timer {
-            last: Instant::now(),
             duration,
+            last: Some(clock.now()),
+            clock,
         }
     }
 
     /// Reset the Timer to stop ringing and wait till 'Duration' has elapsed
     /// again
     pub fn reset(&mut self) {
-        self.last = Instant::now();
+        self.last = Some(self.clock.now());
     }
 
     /// Gets whether or not the Timer is "Ringing" (i.e. the given Duration has
     /// elapsed since the last "reset")
     pub fn ringing(&self) -> bool {
-        Instant::now().saturating_duration_since(self.last) > self.duration
+        match &self.last {
+            Some(last) => self.clock.elapsed(last) > self.duration,
+            None => true,
+        }
     }
 
     /// Manually causes the Timer to enter into a "Ringing" state
     pub fn ring_manual(&mut self) {
-        self.last -= self.duration;
+        self.last = None;
     }
 }
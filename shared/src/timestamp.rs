@@ -0,0 +1,141 @@
+use std::{
+    ops::{Add, Sub},
+    time::Duration,
+};
+
+use super::impls::unix_millis;
+
+/// A Timestamp for a moment in time that can be read/written to/from a byte
+/// stream. Measured in milliseconds since the Unix epoch on every platform,
+/// so Timestamps can be compared between client & server
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Timestamp {
+    time: u64,
+}
+
+impl Timestamp {
+    /// The number of bytes written by `to_bytes()`
+    pub const BYTES: usize = 8;
+
+    /// Get a Timestamp for the current moment
+    pub fn now() -> Self {
+        Timestamp {
+            time: unix_millis(),
+        }
+    }
+
+    /// Creates a Timestamp from a number of milliseconds since the Unix epoch
+    pub fn from_millis(millis: u64) -> Self {
+        Timestamp { time: millis }
+    }
+
+    /// Returns the number of milliseconds since the Unix epoch
+    pub fn as_millis(&self) -> u64 {
+        self.time
+    }
+
+    /// Convert to u64, in milliseconds since the Unix epoch
+    pub fn to_u64(&self) -> u64 {
+        self.time
+    }
+
+    /// Convert from u64, in milliseconds since the Unix epoch
+    pub fn from_u64(value: &u64) -> Self {
+        Self { time: *value }
+    }
+
+    /// Writes the Timestamp as big-endian bytes
+    pub fn to_bytes(&self) -> [u8; Timestamp::BYTES] {
+        self.time.to_be_bytes()
+    }
+
+    /// Reads a Timestamp written by `to_bytes()`
+    pub fn from_bytes(bytes: [u8; Timestamp::BYTES]) -> Self {
+        Timestamp {
+            time: u64::from_be_bytes(bytes),
+        }
+    }
+
+    /// Returns the time elapsed from the given earlier Timestamp to this one,
+    /// or None if it is later
+    pub fn checked_duration_since(&self, earlier: Timestamp) -> Option<Duration> {
+        self.time
+            .checked_sub(earlier.time)
+            .map(Duration::from_millis)
+    }
+
+    /// Returns the time elapsed from the given earlier Timestamp to this one,
+    /// or zero if it is later
+    pub fn saturating_duration_since(&self, earlier: Timestamp) -> Duration {
+        self.checked_duration_since(earlier)
+            .unwrap_or(Duration::ZERO)
+    }
+}
+
+impl From<Duration> for Timestamp {
+    /// Creates a Timestamp from the time since the Unix epoch
+    fn from(since_epoch: Duration) -> Self {
+        Timestamp::from_millis(since_epoch.as_millis() as u64)
+    }
+}
+
+impl From<Timestamp> for Duration {
+    /// Gets the time since the Unix epoch
+    fn from(timestamp: Timestamp) -> Self {
+        Duration::from_millis(timestamp.time)
+    }
+}
+
+impl Add<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    fn add(self, duration: Duration) -> Timestamp {
+        Timestamp::from_millis(self.time + duration.as_millis() as u64)
+    }
+}
+
+impl Sub<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    fn sub(self, duration: Duration) -> Timestamp {
+        Timestamp::from_millis(self.time.saturating_sub(duration.as_millis() as u64))
+    }
+}
+
+impl Sub for Timestamp {
+    type Output = Duration;
+
+    /// Returns the time elapsed between the Timestamps, which is zero if the
+    /// right hand side is later
+    fn sub(self, earlier: Timestamp) -> Duration {
+        self.saturating_duration_since(earlier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Timestamp;
+
+    #[test]
+    fn serializes_and_compares() {
+        let timestamp = Timestamp::from_millis(1_650_000_000_123);
+        assert_eq!(Timestamp::from_bytes(timestamp.to_bytes()), timestamp);
+
+        let later = timestamp + Duration::from_millis(250);
+        assert!(later > timestamp);
+        assert_eq!(later - timestamp, Duration::from_millis(250));
+        assert_eq!(timestamp - later, Duration::ZERO);
+        assert_eq!(
+            later.checked_duration_since(timestamp),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(timestamp.checked_duration_since(later), None);
+        assert_eq!(later - Duration::from_millis(250), timestamp);
+
+        let since_epoch: Duration = timestamp.into();
+        assert_eq!(Timestamp::from(since_epoch), timestamp);
+        assert!(Timestamp::now() > timestamp);
+    }
+}